@@ -0,0 +1,147 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::function::LoxFunction;
+use crate::gc::{self, Trace, Visit};
+use crate::interner::{intern, Symbol};
+use crate::map::{self, Map};
+use crate::tokens::Value;
+
+/// a class declared in lox code
+/// the class itself has methods and fields too, which are inherited by subclasses
+pub struct LoxClass {
+    pub name: Symbol,
+    pub superclass: Option<Rc<LoxClass>>,
+    pub methods: HashMap<Symbol, Rc<LoxFunction>>,
+    pub class_methods: HashMap<Symbol, Rc<LoxFunction>>,
+    pub fields: RefCell<Map>,
+}
+
+impl LoxClass {
+    /// looks for the method in the class and then in its superclasses
+    pub fn find_method(&self, name: &Symbol) -> Option<Rc<LoxFunction>> {
+        if let Some(method) = self.methods.get(name) {
+            return Some(Rc::clone(method));
+        }
+        self.superclass.as_ref().and_then(|superclass| superclass.find_method(name))
+    }
+
+    /// looks for the class method in the class and then in its superclasses
+    pub fn find_class_method(&self, name: &Symbol) -> Option<Rc<LoxFunction>> {
+        if let Some(method) = self.class_methods.get(name) {
+            return Some(Rc::clone(method));
+        }
+        self.superclass.as_ref().and_then(|superclass| superclass.find_class_method(name))
+    }
+
+    /// looks for the field in the class and then in its superclasses, assigning it always sets it on the class
+    pub fn field(&self, name: &Symbol) -> Option<Value> {
+        if let Some(value) = field(&self.fields, name) {
+            return Some(value);
+        }
+        self.superclass.as_ref().and_then(|superclass| superclass.field(name))
+    }
+
+    pub fn set(&self, name: &Symbol, value: Value) {
+        set(&self.fields, name, value);
+    }
+
+    /// calling the class takes the arguments of init, or none if there is no init
+    pub fn arity(&self) -> usize {
+        self.find_method(&intern("init")).map_or(0, |initializer| initializer.declaration.params.len())
+    }
+}
+
+/// a set of methods that classes can include with the class Name with Trait syntax
+pub struct LoxTrait {
+    pub name: Symbol,
+    pub methods: HashMap<Symbol, Rc<LoxFunction>>,
+}
+
+/// the error for two traits of a class that define the same method, which the class doesn't define itself
+pub fn conflict(first: &str, second: &str, method: &str) -> String {
+    format!("Traits {} and {} both define '{}', the class must override it.", first, second, method)
+}
+
+/// an object created by calling a class
+/// the fields are kept in the order in which they were first assigned
+pub struct LoxInstance {
+    pub class: Rc<LoxClass>,
+    pub fields: RefCell<Map>,
+}
+
+impl LoxInstance {
+    pub fn new(class: Rc<LoxClass>) -> Rc<LoxInstance> {
+        gc::manage(LoxInstance { class, fields: RefCell::new(Map::default()) })
+    }
+
+    pub fn field(&self, name: &Symbol) -> Option<Value> {
+        field(&self.fields, name)
+    }
+
+    pub fn set(&self, name: &Symbol, value: Value) {
+        set(&self.fields, name, value);
+    }
+}
+
+impl Trace for LoxClass {
+    fn trace(&self, visit: &mut Visit) -> bool {
+        let fields = match self.fields.try_borrow() {
+            Ok(fields) => fields,
+            Err(_) => return false,
+        };
+        if let Some(superclass) = &self.superclass {
+            gc::trace_rc(superclass, visit);
+        }
+        for method in self.methods.values().chain(self.class_methods.values()) {
+            gc::trace_rc(method, visit);
+        }
+        map::trace(&fields, visit);
+        true
+    }
+
+    fn clear(&self) {
+        clear(&self.fields);
+    }
+}
+
+impl Trace for LoxTrait {
+    fn trace(&self, visit: &mut Visit) -> bool {
+        for method in self.methods.values() {
+            gc::trace_rc(method, visit);
+        }
+        true
+    }
+}
+
+impl Trace for LoxInstance {
+    fn trace(&self, visit: &mut Visit) -> bool {
+        let fields = match self.fields.try_borrow() {
+            Ok(fields) => fields,
+            Err(_) => return false,
+        };
+        gc::trace_rc(&self.class, visit);
+        map::trace(&fields, visit);
+        true
+    }
+
+    fn clear(&self) {
+        clear(&self.fields);
+    }
+}
+
+fn field(fields: &RefCell<Map>, name: &Symbol) -> Option<Value> {
+    // text keys are always valid
+    fields.borrow().get(&Value::Text(name.clone())).unwrap()
+}
+
+fn set(fields: &RefCell<Map>, name: &Symbol, value: Value) {
+    fields.borrow_mut().insert(Value::Text(name.clone()), value).unwrap();
+}
+
+fn clear(fields: &RefCell<Map>) {
+    if let Ok(mut fields) = fields.try_borrow_mut() {
+        *fields = Map::default();
+    }
+}
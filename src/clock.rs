@@ -0,0 +1,92 @@
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::interner::intern;
+use crate::native::NativeFunction;
+use crate::tokens::Value;
+
+/// the functions for the current time, sleeping and formatting times
+pub fn natives() -> Vec<NativeFunction> {
+    vec![
+        // seconds since the epoch with a fraction, like the clock() of the reference implementation
+        NativeFunction::new("clock", 0, |_, _| {
+            Ok(Value::Numeric(since_epoch().as_secs_f64()))
+        }),
+        // whole seconds since the epoch
+        NativeFunction::new("time", 0, |_, _| {
+            Ok(Value::Numeric(since_epoch().as_secs() as f64))
+        }),
+        NativeFunction::new("sleep", 1, |_, arguments| {
+            // infinite and huge numbers don't fit in a duration
+            let duration = match arguments[0] {
+                Value::Numeric(milliseconds) => Duration::try_from_secs_f64(milliseconds / 1000.0).ok(),
+                _ => None,
+            };
+            return match duration {
+                Some(duration) => {
+                    thread::sleep(duration);
+                    Ok(Value::None)
+                }
+                None => Err(String::from("Argument of sleep() must be a number of milliseconds.")),
+            };
+        }),
+        // formats seconds since the epoch as a UTC time, see format
+        NativeFunction::new("formatTime", 2, |_, arguments| {
+            return match (&arguments[0], &arguments[1]) {
+                // times outside of the range of i64 are rejected instead of saturating
+                (Value::Numeric(seconds), Value::Text(pattern)) if seconds.floor() >= i64::MIN as f64
+                    && seconds.floor() < i64::MAX as f64 => {
+                    Ok(Value::Text(intern(&format(seconds.floor() as i64, pattern)?)))
+                }
+                _ => Err(String::from("formatTime() needs a time in seconds and a format string.")),
+            };
+        }),
+    ]
+}
+
+fn since_epoch() -> Duration {
+    // a clock set before 1970 counts as the epoch
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+/// replaces %Y (year), %m (month), %d (day), %H (hour), %M (minute), %S (second) and %% in the pattern
+fn format(seconds: i64, pattern: &str) -> Result<String, String> {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+    let time_of_day = seconds.rem_euclid(86400);
+
+    let mut result = String::new();
+    let mut characters = pattern.chars();
+    while let Some(character) = characters.next() {
+        if character != '%' {
+            result.push(character);
+            continue;
+        }
+        match characters.next() {
+            Some('Y') => result.push_str(&format!("{:04}", year)),
+            Some('m') => result.push_str(&format!("{:02}", month)),
+            Some('d') => result.push_str(&format!("{:02}", day)),
+            Some('H') => result.push_str(&format!("{:02}", time_of_day / 3600)),
+            Some('M') => result.push_str(&format!("{:02}", time_of_day / 60 % 60)),
+            Some('S') => result.push_str(&format!("{:02}", time_of_day % 60)),
+            Some('%') => result.push('%'),
+            Some(other) => return Err(format!("Unknown time format '%{}'.", other)),
+            None => return Err(String::from("Time format ends with '%'.")),
+        }
+    }
+    Ok(result)
+}
+
+/// the year, month and day of the given number of days since 1970-01-01,
+/// using the algorithm from Howard Hinnant's "chrono-Compatible Low-Level Date Algorithms"
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,94 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::gc::{self, Trace, Visit};
+use crate::interner::Symbol;
+use crate::interpreter::RuntimeError;
+use crate::tokens::{Token, Value};
+
+/// the variables of one scope, which can look up variables in the enclosing scopes
+pub struct Environment {
+    values: HashMap<Symbol, Value>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    /// creates a new scope, the global scope has no enclosing scope
+    pub fn new(enclosing: Option<Rc<RefCell<Environment>>>) -> Rc<RefCell<Environment>> {
+        gc::manage(RefCell::new(Environment { values: HashMap::new(), enclosing }))
+    }
+
+    /// defines a variable, redefining an existing one in the same scope is allowed
+    pub fn define(&mut self, name: Symbol, value: Value) {
+        self.values.insert(name, value);
+    }
+
+    /// looks up a variable of this scope, without looking in the enclosing scopes
+    pub fn get_local(&self, name: &Symbol) -> Option<Value> {
+        self.values.get(name).cloned()
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Value, RuntimeError> {
+        if let Some(value) = self.values.get(&name.lexeme) {
+            return Ok(value.clone());
+        }
+
+        return match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().get(name),
+            None => Err(RuntimeError::new(name, &format!("Undefined variable '{}'.", name.lexeme))),
+        };
+    }
+
+    /// looks up the variable starting in the scope the given number of scopes out, as determined by the resolver
+    pub fn get_at(&self, depth: usize, name: &Token) -> Result<Value, RuntimeError> {
+        return match (depth, &self.enclosing) {
+            (0, _) | (_, None) => self.get(name),
+            (_, Some(enclosing)) => enclosing.borrow().get_at(depth - 1, name),
+        };
+    }
+
+    /// assigns to an existing variable, it is an error if it doesn't exist
+    pub fn assign(&mut self, name: &Token, value: Value) -> Result<(), RuntimeError> {
+        if let Some(variable) = self.values.get_mut(&name.lexeme) {
+            *variable = value;
+            return Ok(());
+        }
+
+        return match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => Err(RuntimeError::new(name, &format!("Undefined variable '{}'.", name.lexeme))),
+        };
+    }
+
+    /// assigns to the variable starting in the scope the given number of scopes out, as determined by the resolver
+    pub fn assign_at(&mut self, depth: usize, name: &Token, value: Value) -> Result<(), RuntimeError> {
+        return match (depth, &self.enclosing) {
+            (0, _) | (_, None) => self.assign(name, value),
+            (_, Some(enclosing)) => enclosing.borrow_mut().assign_at(depth - 1, name, value),
+        };
+    }
+}
+
+impl Trace for RefCell<Environment> {
+    fn trace(&self, visit: &mut Visit) -> bool {
+        let environment = match self.try_borrow() {
+            Ok(environment) => environment,
+            Err(_) => return false,
+        };
+        for value in environment.values.values() {
+            gc::trace_value(value, visit);
+        }
+        if let Some(enclosing) = &environment.enclosing {
+            gc::trace_rc(enclosing, visit);
+        }
+        true
+    }
+
+    fn clear(&self) {
+        if let Ok(mut environment) = self.try_borrow_mut() {
+            environment.values.clear();
+            environment.enclosing = None;
+        }
+    }
+}
@@ -1,52 +1,174 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::statement::{Function, StmtVisitor};
 use crate::tokens::{Token, Value};
 
 pub trait Visitor<R> {
     fn visit_expr(&mut self, expr: &Expr) -> R;
 }
 
+/// the number of scopes between where a variable is used and where it is declared, filled in by the resolver
+/// for a global variable it is the number of scopes around the use, which leads to the scope of the file
+pub type Depth = Cell<usize>;
+
 #[derive(Debug, PartialOrd, PartialEq)]
 pub enum Expr {
+    Assign(Token, Box<Expr>, Depth),
     Binary(Box<Expr>, Token, Box<Expr>),
+    // callee, closing parenthesis, arguments
+    Call(Box<Expr>, Token, Vec<Expr>),
+    // target, operator like +=, value
+    CompoundAssign(Box<Expr>, Token, Box<Expr>),
+    // condition, then branch, else branch
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    Get(Box<Expr>, Token),
     Grouping(Box<Expr>),
+    // target, ++ or --, true when the operator comes after the target
+    Increment(Box<Expr>, Token, bool),
+    // string parts and the interpolated expressions, in order
+    Interpolation(Vec<Expr>),
+    // object, opening bracket, index
+    Index(Box<Expr>, Token, Box<Expr>),
+    // object, opening bracket, index, assigned value
+    IndexSet(Box<Expr>, Token, Box<Expr>, Box<Expr>),
+    // an anonymous function, the body of the arrow form returns its expression
+    Lambda(Rc<Function>),
+    List(Vec<Expr>),
     Literal(Value),
+    // left operand, and or or, right operand
+    Logical(Box<Expr>, Token, Box<Expr>),
+    // opening brace, key value pairs
+    Map(Token, Vec<(Expr, Expr)>),
+    // object, opening bracket, optional start and end
+    Slice(Box<Expr>, Token, Option<Box<Expr>>, Option<Box<Expr>>),
+    // object, property name, assigned value
+    Set(Box<Expr>, Token, Box<Expr>),
+    // keyword, method name
+    Super(Token, Token, Depth),
+    This(Token, Depth),
     Unary(Token, Box<Expr>),
+    Variable(Token, Depth),
 }
 
+impl Expr {
+    /// the name of a variable, the parser only uses variables where a class or trait is named
+    pub fn name(&self) -> &Token {
+        return match self {
+            Expr::Variable(name, _) => name,
+            _ => unreachable!("only variables have a name"),
+        };
+    }
+}
+
+/// prints the syntax tree, for debugging and in tests
+#[allow(dead_code)]
 pub struct AstPrinter {}
 
 impl Visitor<String> for AstPrinter {
     fn visit_expr(&mut self, expr: &Expr) -> String {
         return match expr {
+            Expr::Assign(name, value, _) => {
+                self.parenthesize(&format!("= {}", name.lexeme), &[value])
+            }
             Expr::Binary(left, operator, right) => {
                 self.parenthesize(&operator.lexeme, &[left, right])
             }
+            Expr::Call(callee, _, arguments) => {
+                let mut expressions: Vec<&Expr> = vec![callee];
+                expressions.extend(arguments.iter());
+                self.parenthesize("call", &expressions)
+            }
+            Expr::CompoundAssign(target, operator, value) => {
+                self.parenthesize(&operator.lexeme, &[target, value])
+            }
+            Expr::Conditional(condition, then_branch, else_branch) => {
+                self.parenthesize("?:", &[condition, then_branch, else_branch])
+            }
+            Expr::Get(object, name) => {
+                self.parenthesize(&format!(". {}", name.lexeme), &[object])
+            }
             Expr::Grouping(expression) => {
                 self.parenthesize("group", &[expression])
             }
+            Expr::Increment(target, operator, postfix) => {
+                let name = if *postfix { format!("post{}", operator.lexeme) } else { operator.lexeme.to_string() };
+                self.parenthesize(&name, &[target])
+            }
+            Expr::Interpolation(parts) => {
+                let expressions: Vec<&Expr> = parts.iter().collect();
+                self.parenthesize("interpolate", &expressions)
+            }
+            Expr::Index(object, _, index) => {
+                self.parenthesize("index", &[object, index])
+            }
+            Expr::IndexSet(object, _, index, value) => {
+                self.parenthesize("index=", &[object, index, value])
+            }
+            Expr::Lambda(function) => {
+                let params: Vec<&str> = function.params.iter().map(|param| param.lexeme.as_str()).collect();
+                let mut buf = format!("(lambda ({})", params.join(" "));
+                for statement in &function.body {
+                    buf.push(' ');
+                    buf.push_str(&self.visit_stmt(statement));
+                }
+                buf.push(')');
+                buf
+            }
+            Expr::List(elements) => {
+                let expressions: Vec<&Expr> = elements.iter().collect();
+                self.parenthesize("list", &expressions)
+            }
             Expr::Literal(value) => {
-                format!("{:?}", value)
+                value.to_string()
+            }
+            Expr::Logical(left, operator, right) => {
+                self.parenthesize(&operator.lexeme, &[left, right])
+            }
+            Expr::Map(_, entries) => {
+                let expressions: Vec<&Expr> = entries.iter().flat_map(|(key, value)| vec![key, value]).collect();
+                self.parenthesize("map", &expressions)
+            }
+            Expr::Slice(object, _, start, end) => {
+                // a missing bound is printed as _
+                let bound = |printer: &mut AstPrinter, bound: &Option<Box<Expr>>| {
+                    bound.as_ref().map_or(String::from("_"), |expr| printer.visit_expr(expr))
+                };
+                let start = bound(self, start);
+                let end = bound(self, end);
+                format!("(slice {} {} {})", self.visit_expr(object), start, end)
+            }
+            Expr::Set(object, name, value) => {
+                self.parenthesize(&format!(".= {}", name.lexeme), &[object, value])
+            }
+            Expr::Super(_, method, _) => {
+                format!("(super {})", method.lexeme)
+            }
+            Expr::This(_, _) => {
+                String::from("this")
             }
             Expr::Unary(operator, right) => {
                 self.parenthesize(&operator.lexeme, &[right])
             }
+            Expr::Variable(name, _) => {
+                name.lexeme.to_string()
+            }
         };
     }
 }
 
+#[allow(dead_code)]
 impl AstPrinter {
     fn parenthesize(&mut self, name: &str, expressions: &[&Expr]) -> String {
         let mut buf = String::from("(");
         buf.push_str(name);
-        buf.push_str(" ");
 
-        let mut index = 0;
         for expr in expressions {
-            if index > 0 { buf.push_str(" "); }
+            buf.push(' ');
             buf.push_str(&self.visit_expr(expr));
-            index += 1;
         }
 
-        buf.push_str(")");
+        buf.push(')');
         buf
     }
-}
\ No newline at end of file
+}
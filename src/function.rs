@@ -0,0 +1,34 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::gc::{self, Trace, Visit};
+use crate::interner::intern;
+use crate::statement::Function;
+use crate::tokens::Value;
+
+/// a function declared in lox code
+/// the closure is the scope the declaration was executed in, which the body can keep using after it has ended
+/// a function stored in its own closure is a reference cycle, which the collector frees once it can't be reached
+pub struct LoxFunction {
+    pub declaration: Rc<Function>,
+    pub closure: Rc<RefCell<Environment>>,
+    // init methods always return the instance
+    pub is_initializer: bool,
+}
+
+impl LoxFunction {
+    /// the method as a function with this bound to the instance, in a scope between the closure and the body
+    pub fn bind(&self, instance: Value) -> Rc<LoxFunction> {
+        let environment = Environment::new(Some(Rc::clone(&self.closure)));
+        environment.borrow_mut().define(intern("this"), instance);
+        gc::manage(LoxFunction { declaration: Rc::clone(&self.declaration), closure: environment, is_initializer: self.is_initializer })
+    }
+}
+
+impl Trace for LoxFunction {
+    fn trace(&self, visit: &mut Visit) -> bool {
+        gc::trace_rc(&self.closure, visit);
+        true
+    }
+}
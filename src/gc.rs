@@ -0,0 +1,160 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use crate::tokens::Value;
+
+/// the first collection runs when this many objects are alive
+const FIRST_COLLECTION: usize = 1024;
+
+/// after a collection the next one runs when the objects alive have grown to this many times the ones that survived
+const GROWTH_FACTOR: usize = 2;
+
+thread_local! {
+    // every managed object that may still be alive, weakly, so the heap doesn't keep anything alive itself
+    static HEAP: RefCell<Heap> = RefCell::new(Heap {
+        objects: Vec::new(),
+        next_check: FIRST_COLLECTION,
+        next_collection: FIRST_COLLECTION,
+        stress: false,
+    });
+}
+
+struct Heap {
+    // most objects are freed by Rc as soon as they are dropped, they stay in the list until the next check
+    objects: Vec<Weak<dyn Trace>>,
+    // the length of the list at which the freed objects are removed from it, to see how many are alive
+    next_check: usize,
+    // the number of objects alive at which the next collection runs
+    next_collection: usize,
+    // collect on every allocation, to test the collector
+    stress: bool,
+}
+
+impl Heap {
+    /// removes the objects that have been freed, and returns whether enough are alive to collect
+    fn check(&mut self) -> bool {
+        self.objects.retain(|object| object.strong_count() > 0);
+        self.next_check = self.objects.len() + FIRST_COLLECTION.max(self.objects.len());
+        self.objects.len() >= self.next_collection
+    }
+}
+
+/// the callback that receives the address of every managed object that an object references
+pub type Visit<'a> = dyn FnMut(*const ()) + 'a;
+
+/// an object on the managed heap, which can reference other managed objects and so be part of a cycle
+/// environments, functions, classes, instances, traits, lists, maps and errors are managed
+pub trait Trace {
+    /// visits every managed object this object holds a reference to, once for every reference
+    /// returns false without visiting anything when the object is being changed and can't be read
+    fn trace(&self, visit: &mut Visit) -> bool;
+
+    /// drops the references this object holds, which breaks the cycles it is part of
+    /// objects that can't change don't need to, because every cycle goes through one that can
+    fn clear(&self) {}
+}
+
+/// visits the object the value references, if it is managed
+/// natives, iterators and modules are not, whatever they reference stays alive while they are
+pub fn trace_value(value: &Value, visit: &mut Visit) {
+    match value {
+        Value::List(list) => trace_rc(list, visit),
+        Value::Map(map) => trace_rc(map, visit),
+        Value::Function(function) => trace_rc(function, visit),
+        Value::Class(class) => trace_rc(class, visit),
+        Value::Instance(instance) => trace_rc(instance, visit),
+        Value::Trait(used) => trace_rc(used, visit),
+        Value::Error(error) => trace_rc(error, visit),
+        _ => {}
+    }
+}
+
+pub fn trace_rc<T: ?Sized>(object: &Rc<T>, visit: &mut Visit) {
+    visit(Rc::as_ptr(object) as *const ());
+}
+
+/// puts the object on the managed heap, which runs a collection first when the heap has grown enough
+pub fn manage<T: Trace + 'static>(object: T) -> Rc<T> {
+    let object = Rc::new(object);
+    let weak: Weak<dyn Trace> = Rc::downgrade(&object) as Weak<dyn Trace>;
+    let due = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.push(weak);
+        heap.stress || (heap.objects.len() >= heap.next_check && heap.check())
+    });
+    if due {
+        collect();
+    }
+    object
+}
+
+/// makes every allocation run a collection, which is slow, but finds objects that are freed while in use
+pub fn stress() {
+    HEAP.with(|heap| heap.borrow_mut().stress = true);
+}
+
+/// frees the objects that can't be reached anymore, which Rc alone can't free when they are part of a cycle
+///
+/// the roots are the objects referenced from outside of the heap: the current environment and the environments of the
+/// calls around it, the globals, the module table, the iterators of running loops, natives holding values, and the
+/// values the interpreter is working on in the middle of an expression. they are found by their reference counts,
+/// so they don't need to be listed, and a collection can run at any allocation:
+/// 1. every object starts with its reference count, minus the references that other managed objects hold
+/// 2. the objects with references left over are referenced from outside, they are the roots and are marked
+/// 3. the objects the marked objects reference are marked too, using a stack of gray objects still to be traced
+/// 4. the objects that are not marked are only referenced by each other, clearing them breaks their cycles,
+///    and dropping the last references frees them
+///
+/// interned strings are not managed, the intern table only holds them weakly and removes them when they are dropped
+pub fn collect() {
+    // the heap can't be borrowed while freeing the garbage, which would be the case if an object allocated
+    let objects: Vec<Rc<dyn Trace>> = HEAP.with(|heap| heap.borrow().objects.iter().filter_map(Weak::upgrade).collect());
+    let index: HashMap<*const (), usize> = objects.iter().enumerate()
+        .map(|(position, object)| (Rc::as_ptr(object) as *const (), position))
+        .collect();
+
+    // the list above holds one reference to every object
+    let mut outside: Vec<usize> = objects.iter().map(|object| Rc::strong_count(object) - 1).collect();
+    let mut readable = Vec::with_capacity(objects.len());
+    for object in &objects {
+        readable.push(object.trace(&mut |child| {
+            if let Some(&position) = index.get(&child) {
+                outside[position] -= 1;
+            }
+        }));
+    }
+
+    // an object that is being changed is in use, so it is a root, and everything it references counts as outside
+    let mut marked: Vec<bool> = outside.iter().zip(&readable).map(|(outside, readable)| *outside > 0 || !readable).collect();
+    let mut gray: Vec<usize> = (0..objects.len()).filter(|position| marked[*position]).collect();
+    while let Some(position) = gray.pop() {
+        objects[position].trace(&mut |child| {
+            if let Some(&child) = index.get(&child) {
+                if !marked[child] {
+                    marked[child] = true;
+                    gray.push(child);
+                }
+            }
+        });
+    }
+
+    for (object, marked) in objects.iter().zip(&marked) {
+        if !marked {
+            object.clear();
+        }
+    }
+    drop(objects);
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.check();
+        heap.next_collection = FIRST_COLLECTION.max(heap.objects.len() * GROWTH_FACTOR);
+    });
+}
+
+/// the number of managed objects that are alive
+#[cfg(test)]
+pub fn live_objects() -> usize {
+    HEAP.with(|heap| heap.borrow().objects.iter().filter(|object| object.strong_count() > 0).count())
+}
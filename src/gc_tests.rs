@@ -0,0 +1,108 @@
+use crate::gc;
+use crate::interner::intern;
+use crate::interpreter::Interpreter;
+use crate::parser::parse;
+use crate::resolver::resolve;
+use crate::scanner::scan_tokens;
+use crate::tokens::{Token, Value};
+use crate::tokens::TokenType::IDENTIFIER;
+
+/// runs the program with the interpreter and returns the value of its global variable result
+fn run_with(source: &str, interpreter: &mut Interpreter) -> Value {
+    let tokens = scan_tokens(source).unwrap();
+    let statements = parse(tokens).unwrap();
+    resolve(&statements).unwrap();
+
+    interpreter.interpret(&statements).unwrap();
+
+    let result = Token { token_type: IDENTIFIER, lexeme: intern("result"), literal: Value::None, line: 1 };
+    let value = interpreter.globals.borrow().get(&result).unwrap();
+    value
+}
+
+/// runs the declarations, then the program, which leaves cycles behind,
+/// and returns how many more objects are alive after a collection than after the declarations
+fn garbage_left(declarations: &str, source: &str) -> usize {
+    let mut interpreter = Interpreter::new();
+    run_with(&format!("var result = nil; {}", declarations), &mut interpreter);
+    gc::collect();
+    let before = gc::live_objects();
+
+    run_with(source, &mut interpreter);
+    gc::collect();
+    gc::live_objects().saturating_sub(before)
+}
+
+#[test]
+fn test_closure_cycles_are_freed() {
+    let declarations = "
+        fun outer() {
+            var big = [1, 2, 3];
+            fun inner() { return big; }
+        }";
+    assert_eq!(garbage_left(declarations, "for (var i = 0; i < 100; i = i + 1) outer();"), 0);
+}
+
+#[test]
+fn test_instance_cycles_are_freed() {
+    let declarations = "
+        class Node {
+            init() {
+                this.method = this.get;
+                this.lambda = fun () { return this; };
+                this.me = this;
+            }
+            get() { return 1; }
+        }";
+    assert_eq!(garbage_left(declarations, "for (var i = 0; i < 100; i = i + 1) Node();"), 0);
+}
+
+#[test]
+fn test_list_and_map_cycles_are_freed() {
+    let source = "
+        for (var i = 0; i < 100; i = i + 1) {
+            var list = [];
+            list.push(list);
+            var map = {};
+            map[\"self\"] = map;
+        }";
+    assert_eq!(garbage_left("", source), 0);
+}
+
+#[test]
+fn test_reachable_objects_survive_a_collection() {
+    let mut interpreter = Interpreter::new();
+    run_with("
+        fun counter() {
+            var count = 0;
+            fun increment() { count = count + 1; return count; }
+            return increment;
+        }
+        class Node { init() { this.me = this; this.value = 42; } }
+        var increment = counter();
+        increment();
+        var node = Node();
+        var result = nil;", &mut interpreter);
+    gc::collect();
+
+    let result = run_with("result = [increment(), node.me.me.value];", &mut interpreter);
+    assert_eq!(result.to_string(), "[2, 42]");
+}
+
+#[test]
+fn test_stress() {
+    gc::stress();
+    let mut interpreter = Interpreter::new();
+    let result = run_with("
+        class Node {
+            init(value) { this.value = value; this.me = this; }
+            sum(other) { return this.value + other.value; }
+        }
+        fun make(value) {
+            var nodes = [Node(value), Node(value + 1)];
+            return fun () { return nodes[0].sum(nodes[1]); };
+        }
+        var result = [];
+        for (var i = 0; i < 10; i = i + 1) result.push(make(i)());", &mut interpreter);
+    assert_eq!(result.to_string(), "[1, 3, 5, 7, 9, 11, 13, 15, 17, 19]");
+}
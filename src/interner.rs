@@ -0,0 +1,108 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+thread_local! {
+    // every distinct string handed out by intern that is still in use, shared by all tokens and values
+    // a string is removed when its last symbol is dropped, so the strings built at runtime don't pile up
+    static STRINGS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// returns the one shared symbol for the given text,
+/// adding it to the table the first time it is seen
+pub fn intern(text: &str) -> Symbol {
+    STRINGS.with(|strings| {
+        let mut strings = strings.borrow_mut();
+        if let Some(existing) = strings.get(text) {
+            return Symbol(Rc::clone(existing));
+        }
+
+        let symbol: Rc<str> = Rc::from(text);
+        strings.insert(Rc::clone(&symbol));
+        Symbol(symbol)
+    })
+}
+
+/// the number of strings in the table
+#[cfg(test)]
+pub fn table_size() -> usize {
+    STRINGS.with(|strings| strings.borrow().len())
+}
+
+/// an interned string
+/// cloning is cheap and two symbols are equal iff they point to the same interned text,
+/// so they can be used as keys in lookup tables without hashing the text
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for Symbol {
+    fn drop(&mut self) {
+        // the table holds the other reference, this is the last symbol for the text
+        if Rc::strong_count(&self.0) == 2 {
+            // the table is gone when the thread is shutting down
+            let _ = STRINGS.try_with(|strings| strings.borrow_mut().remove(&*self.0));
+        }
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.0.as_ptr() as usize).hash(state);
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Symbol) -> Option<Ordering> {
+        Some(self.as_str().cmp(other.as_str()))
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
@@ -0,0 +1,1138 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::class::{self, LoxClass, LoxInstance, LoxTrait};
+use crate::clock;
+use crate::environment::Environment;
+use crate::expression::{Expr, Visitor};
+use crate::function::LoxFunction;
+use crate::gc::{self, Trace, Visit};
+use crate::system;
+use crate::interner::{intern, Symbol};
+use crate::iterator;
+use crate::json;
+use crate::list;
+use crate::map::{self, Map};
+use crate::math;
+use crate::module::{self, Module};
+use crate::native::NativeFunction;
+use crate::parser::parse;
+use crate::random;
+use crate::resolver::resolve;
+use crate::scanner::scan_tokens;
+use crate::statement::{Class, Function, Stmt, StmtVisitor};
+use crate::strings;
+use crate::tokens::{Token, TokenType, Value};
+use crate::tokens::TokenType::*;
+use crate::types;
+
+/// calls deeper than this are reported as a stack overflow, instead of overflowing the stack of the interpreter
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// error raised while evaluating, the token is used to report the line
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub token: Token,
+    pub message: String,
+    // the error object of a throw statement, errors of the interpreter itself get one when they are caught
+    pub error: Option<Rc<LoxError>>,
+    // the functions and modules the error propagated out of, innermost first, with the line of the call or import
+    pub calls: Vec<(String, usize)>,
+}
+
+impl RuntimeError {
+    pub fn new(token: &Token, message: &str) -> RuntimeError {
+        RuntimeError { token: token.clone(), message: String::from(message), error: Option::None, calls: Vec::new() }
+    }
+
+    /// the error raised by a throw statement, a value that is not an error object is wrapped in one
+    pub fn thrown(token: &Token, value: Value) -> RuntimeError {
+        let error = match value {
+            Value::Error(error) => error,
+            value => gc::manage(LoxError { message: value, line: token.line }),
+        };
+        RuntimeError { token: token.clone(), message: error.message.to_string(), error: Some(error), calls: Vec::new() }
+    }
+
+    /// the error object that a catch clause receives
+    fn into_value(self) -> Value {
+        let line = self.token.line;
+        let message = self.message;
+        Value::Error(self.error.unwrap_or_else(|| gc::manage(LoxError { message: Value::Text(intern(&message)), line })))
+    }
+}
+
+/// the message followed by the stack trace, like
+/// Operands must be numbers.
+/// [line 2] in inner()
+/// [line 5] in outer()
+/// [line 1] in lib.lox
+/// [line 7] in script
+/// a frame that repeats, as with runaway recursion, is shown once with the number of repeats
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        let mut frames = Vec::new();
+        let mut line = self.token.line;
+        for (frame, call_line) in &self.calls {
+            frames.push(format!("[line {}] in {}", line, frame));
+            line = *call_line;
+        }
+        frames.push(format!("[line {}] in script", line));
+
+        let mut index = 0;
+        while index < frames.len() {
+            let repeats = frames[index..].iter().take_while(|frame| **frame == frames[index]).count();
+            write!(f, "\n{}", frames[index])?;
+            if repeats > 1 {
+                write!(f, " (repeated {} more times)", repeats - 1)?;
+            }
+            index += repeats;
+        }
+        Ok(())
+    }
+}
+
+/// an error as lox code sees it, with the properties message and line
+#[derive(Debug)]
+pub struct LoxError {
+    pub message: Value,
+    pub line: usize,
+}
+
+impl Trace for LoxError {
+    fn trace(&self, visit: &mut Visit) -> bool {
+        gc::trace_value(&self.message, visit);
+        true
+    }
+}
+
+/// the reasons to stop executing the statements of a block before its end
+#[derive(Debug)]
+pub enum Unwind {
+    Error(RuntimeError),
+    Break,
+    Continue,
+    Return(Value),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Unwind {
+        Unwind::Error(error)
+    }
+}
+
+type EvalResult = Result<Value, RuntimeError>;
+type ExecResult = Result<(), Unwind>;
+
+/// executes statements and evaluates expressions
+/// the global variables are kept between calls to interpret, which is what the prompt needs
+pub struct Interpreter {
+    // the native functions, which every file can use
+    natives: Rc<RefCell<Environment>>,
+    // the global variables of the script, modules have their own
+    #[allow(dead_code)] // read by the tests
+    pub globals: Rc<RefCell<Environment>>,
+    // the innermost scope
+    environment: Rc<RefCell<Environment>>,
+    // the number of lox functions that are being called
+    depth: usize,
+    // every module that has been imported, by its canonical path
+    modules: HashMap<PathBuf, Rc<Module>>,
+    // the files that are running, the last one is the current file, which is where imports are resolved from
+    loading: Vec<PathBuf>,
+    // false for sandboxed runs, in which the native functions can't access files
+    file_access: bool,
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        let natives = Environment::new(None);
+        let globals = Environment::new(Some(Rc::clone(&natives)));
+        let mut interpreter = Interpreter {
+            natives,
+            environment: Rc::clone(&globals),
+            globals,
+            depth: 0,
+            modules: HashMap::new(),
+            loading: Vec::new(),
+            file_access: true,
+        };
+        interpreter.define_native(iterator::range());
+        // the math functions are globals too
+        let math = math::natives();
+        for (name, value) in &math {
+            interpreter.natives.borrow_mut().define(intern(name), value.clone());
+        }
+        interpreter.define_module("math", math);
+        interpreter.define_module("json", json::natives());
+        let natives = strings::natives().into_iter().chain(system::natives()).chain(clock::natives()).chain(random::natives())
+            .chain(types::natives());
+        for function in natives {
+            interpreter.define_native(function);
+        }
+        interpreter
+    }
+
+    /// an interpreter for the script in the given file, which resolves imports relative to the script
+    /// without one, as in the prompt, they are relative to the working directory
+    pub fn for_script(path: &Path) -> Interpreter {
+        let mut interpreter = Interpreter::new();
+        interpreter.loading.push(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        interpreter
+    }
+
+    /// makes the native functions that read or write files fail,
+    /// and limits imports to the directory of the script and the directories below it
+    pub fn deny_file_access(&mut self) {
+        self.file_access = false;
+    }
+
+    pub fn file_access(&self) -> bool {
+        self.file_access
+    }
+
+    /// makes the native function available as a global variable
+    fn define_native(&mut self, function: NativeFunction) {
+        self.natives.borrow_mut().define(intern(function.name), Value::native(function));
+    }
+
+    /// makes the values available as properties of a built-in module
+    fn define_module(&mut self, name: &str, values: Vec<(&str, Value)>) {
+        let globals = Environment::new(None);
+        for (name, value) in values {
+            globals.borrow_mut().define(intern(name), value);
+        }
+
+        let module = Module { path: PathBuf::from(name), globals };
+        self.natives.borrow_mut().define(intern(name), Value::Module(Rc::new(module)));
+    }
+
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
+        for statement in statements {
+            match self.visit_stmt(statement) {
+                Ok(_) => {}
+                Err(Unwind::Error(error)) => return Err(error),
+                // the resolver doesn't allow break, continue and return outside of loops and functions
+                Err(Unwind::Break) | Err(Unwind::Continue) | Err(Unwind::Return(_)) => return Ok(()),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
+
+impl StmtVisitor<ExecResult> for Interpreter {
+    fn visit_stmt(&mut self, stmt: &Stmt) -> ExecResult {
+        return match stmt {
+            Stmt::Block(statements) => {
+                self.execute_block(statements, Environment::new(Some(Rc::clone(&self.environment))))
+            }
+            Stmt::Break(_) => {
+                Err(Unwind::Break)
+            }
+            Stmt::Class(class) => {
+                let superclass = match &class.superclass {
+                    Some(variable) => match self.visit_expr(variable)? {
+                        Value::Class(superclass) => Some(superclass),
+                        _ => return Err(RuntimeError::new(variable.name(), "Superclass must be a class.").into()),
+                    },
+                    None => None,
+                };
+
+                // the methods of a subclass find the superclass as super, in a scope around them
+                let closure = match &superclass {
+                    Some(superclass) => {
+                        let closure = Environment::new(Some(Rc::clone(&self.environment)));
+                        closure.borrow_mut().define(intern("super"), Value::Class(Rc::clone(superclass)));
+                        closure
+                    }
+                    None => Rc::clone(&self.environment),
+                };
+                let functions = |methods: &[Rc<Function>], initializers: bool| methods.iter().map(|function| {
+                    (function.name.lexeme.clone(), method(function, &closure, initializers))
+                }).collect::<HashMap<_, _>>();
+
+                let declaration = class;
+                let mut methods = self.trait_methods(declaration)?;
+                methods.extend(functions(&declaration.methods, true));
+                let class = gc::manage(LoxClass {
+                    name: declaration.name.lexeme.clone(),
+                    superclass,
+                    methods,
+                    class_methods: functions(&declaration.class_methods, false),
+                    fields: RefCell::new(Map::default()),
+                });
+                self.environment.borrow_mut().define(class.name.clone(), Value::Class(Rc::clone(&class)));
+
+                // the initializers run in the scope around the class, after it is defined so that they can use it
+                for (name, initializer) in &declaration.class_fields {
+                    let value = match initializer {
+                        Some(initializer) => self.visit_expr(initializer)?,
+                        None => Value::None,
+                    };
+                    class.set(&name.lexeme, value);
+                }
+                Ok(())
+            }
+            Stmt::Continue(_) => {
+                Err(Unwind::Continue)
+            }
+            Stmt::Expression(expression) => {
+                self.visit_expr(expression)?;
+                Ok(())
+            }
+            Stmt::ForIn(name, iterable, body) => {
+                let iterable = self.visit_expr(iterable)?;
+                let iterator = self.invoke(&iterable, name, &intern("iterator"))?;
+                let next = intern("next");
+
+                loop {
+                    let element = match &iterator {
+                        Value::Iterator(iterator) => match iterator.next() {
+                            Some(element) => element,
+                            None => break,
+                        },
+                        _ => match self.invoke(&iterator, name, &next)? {
+                            Value::None => break,
+                            element => element,
+                        },
+                    };
+
+                    // every iteration gets a fresh variable
+                    let environment = Environment::new(Some(Rc::clone(&self.environment)));
+                    environment.borrow_mut().define(name.lexeme.clone(), element);
+                    if !keep_looping(self.execute_block(std::slice::from_ref(body), environment))? {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            Stmt::Function(declaration) => {
+                let function = LoxFunction {
+                    declaration: Rc::clone(declaration),
+                    closure: Rc::clone(&self.environment),
+                    is_initializer: false,
+                };
+                self.environment.borrow_mut().define(declaration.name.lexeme.clone(), Value::Function(gc::manage(function)));
+                Ok(())
+            }
+            Stmt::FromImport(path, names) => {
+                let module = self.import(path)?;
+                for name in names {
+                    let value = module.globals.borrow().get_local(&name.lexeme).ok_or_else(|| {
+                        RuntimeError::new(name, &format!("Module '{}' has no variable '{}'.", module.name(), name.lexeme))
+                    })?;
+                    self.environment.borrow_mut().define(name.lexeme.clone(), value);
+                }
+                Ok(())
+            }
+            Stmt::If(condition, then_branch, else_branch) => {
+                if is_truthy(&self.visit_expr(condition)?) {
+                    self.visit_stmt(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.visit_stmt(else_branch)
+                } else {
+                    Ok(())
+                }
+            }
+            Stmt::Import(path, name) => {
+                let module = self.import(path)?;
+                self.environment.borrow_mut().define(name.lexeme.clone(), Value::Module(module));
+                Ok(())
+            }
+            Stmt::Print(expression) => {
+                let value = self.visit_expr(expression)?;
+                println!("{}", self.stringify(&value)?);
+                Ok(())
+            }
+            Stmt::Return(_, value) => {
+                let value = match value {
+                    Some(value) => self.visit_expr(value)?,
+                    None => Value::None,
+                };
+                Err(Unwind::Return(value))
+            }
+            Stmt::Throw(keyword, value) => {
+                let value = self.visit_expr(value)?;
+                Err(Unwind::Error(RuntimeError::thrown(keyword, value)))
+            }
+            Stmt::Trait(declaration) => {
+                let methods = declaration.methods.iter().map(|function| {
+                    (function.name.lexeme.clone(), method(function, &self.environment, true))
+                }).collect();
+                let value = Value::Trait(gc::manage(LoxTrait { name: declaration.name.lexeme.clone(), methods }));
+                self.environment.borrow_mut().define(declaration.name.lexeme.clone(), value);
+                Ok(())
+            }
+            Stmt::Try(body, catch, finally) => {
+                let mut result = self.execute_block(body, Environment::new(Some(Rc::clone(&self.environment))));
+
+                // break, continue and return are not caught
+                if let Some((name, catch)) = catch {
+                    result = match result {
+                        Err(Unwind::Error(error)) => {
+                            let environment = Environment::new(Some(Rc::clone(&self.environment)));
+                            environment.borrow_mut().define(name.lexeme.clone(), error.into_value());
+                            self.execute_block(catch, environment)
+                        }
+                        result => result,
+                    };
+                }
+
+                // the finally clause always runs, an error, break or return in it replaces the outcome of the rest
+                if let Some(finally) = finally {
+                    self.execute_block(finally, Environment::new(Some(Rc::clone(&self.environment))))?;
+                }
+                result
+            }
+            Stmt::Var(name, initializer) => {
+                let value = match initializer {
+                    Some(initializer) => self.visit_expr(initializer)?,
+                    None => Value::None,
+                };
+                self.environment.borrow_mut().define(name.lexeme.clone(), value);
+                Ok(())
+            }
+            Stmt::While(condition, body, increment) => {
+                while is_truthy(&self.visit_expr(condition)?) {
+                    if !keep_looping(self.visit_stmt(body))? {
+                        break;
+                    }
+                    // the increment of a for loop also runs after continue
+                    if let Some(increment) = increment {
+                        self.visit_expr(increment)?;
+                    }
+                }
+                Ok(())
+            }
+        };
+    }
+}
+
+impl Visitor<EvalResult> for Interpreter {
+    fn visit_expr(&mut self, expr: &Expr) -> EvalResult {
+        return match expr {
+            Expr::Assign(name, value, depth) => {
+                let value = self.visit_expr(value)?;
+                self.environment.borrow_mut().assign_at(depth.get(), name, value.clone())?;
+                Ok(value)
+            }
+            Expr::Binary(left, operator, right) => {
+                let left = self.visit_expr(left)?;
+                let right = self.visit_expr(right)?;
+                self.operate(left, operator, right)
+            }
+            Expr::Call(callee, paren, arguments) => {
+                let callee = self.visit_expr(callee)?;
+
+                let mut values = Vec::new();
+                for argument in arguments {
+                    values.push(self.visit_expr(argument)?);
+                }
+
+                self.call(callee, paren, values)
+            }
+            Expr::CompoundAssign(target, operator, value) => {
+                let (_, new) = self.update(target, operator, Some(value))?;
+                Ok(new)
+            }
+            Expr::Conditional(condition, then_branch, else_branch) => {
+                if is_truthy(&self.visit_expr(condition)?) {
+                    self.visit_expr(then_branch)
+                } else {
+                    self.visit_expr(else_branch)
+                }
+            }
+            Expr::Get(object, name) => {
+                let object = self.visit_expr(object)?;
+                self.get(&object, name, &name.lexeme)
+            }
+            Expr::Grouping(expression) => {
+                self.visit_expr(expression)
+            }
+            Expr::Increment(target, operator, postfix) => {
+                let (old, new) = self.update(target, operator, None)?;
+                Ok(if *postfix { old } else { new })
+            }
+            Expr::Interpolation(parts) => {
+                let mut text = String::new();
+                for part in parts {
+                    let value = self.visit_expr(part)?;
+                    text.push_str(&self.stringify(&value)?);
+                }
+                Ok(Value::Text(intern(&text)))
+            }
+            Expr::Index(object, bracket, index) => {
+                let object = self.visit_expr(object)?;
+                let index = self.visit_expr(index)?;
+                self.get_index(&object, bracket, index)
+            }
+            Expr::IndexSet(object, bracket, index, value) => {
+                let object = self.visit_expr(object)?;
+                let index = self.visit_expr(index)?;
+                let value = self.visit_expr(value)?;
+                self.set_index(&object, bracket, index, value.clone())?;
+                Ok(value)
+            }
+            Expr::Lambda(declaration) => {
+                let function = LoxFunction {
+                    declaration: Rc::clone(declaration),
+                    closure: Rc::clone(&self.environment),
+                    is_initializer: false,
+                };
+                Ok(Value::Function(gc::manage(function)))
+            }
+            Expr::List(elements) => {
+                let mut values = Vec::new();
+                for element in elements {
+                    values.push(self.visit_expr(element)?);
+                }
+                Ok(Value::list(values))
+            }
+            Expr::Literal(value) => {
+                Ok(value.clone())
+            }
+            Expr::Logical(left, operator, right) => {
+                // the right operand is only evaluated when the left one doesn't decide, the value is that of the last
+                let left = self.visit_expr(left)?;
+                if is_truthy(&left) == (operator.token_type == OR) {
+                    return Ok(left);
+                }
+                self.visit_expr(right)
+            }
+            Expr::Map(brace, entries) => {
+                let mut map = Map::default();
+                for (key, value) in entries {
+                    let key = self.visit_expr(key)?;
+                    let value = self.visit_expr(value)?;
+                    map.insert(key, value).map_err(|message| RuntimeError::new(brace, &message))?;
+                }
+                Ok(Value::map(map))
+            }
+            Expr::Slice(object, bracket, start, end) => {
+                let object = self.visit_expr(object)?;
+                let start = match start {
+                    Some(start) => Some(self.visit_expr(start)?),
+                    None => None,
+                };
+                let end = match end {
+                    Some(end) => Some(self.visit_expr(end)?),
+                    None => None,
+                };
+                let result = match object {
+                    Value::List(elements) => list::slice(&elements.borrow(), start.as_ref(), end.as_ref()),
+                    Value::Text(text) => strings::slice(&text, start.as_ref(), end.as_ref()),
+                    _ => Err(String::from("Only lists and strings can be sliced.")),
+                };
+                result.map_err(|message| RuntimeError::new(bracket, &message))
+            }
+            Expr::Set(object, name, value) => {
+                let object = self.visit_expr(object)?;
+                let value = self.visit_expr(value)?;
+                set(&object, name, value.clone())?;
+                Ok(value)
+            }
+            Expr::Super(keyword, method, depth) => {
+                let superclass = match self.environment.borrow().get_at(depth.get(), keyword)? {
+                    Value::Class(superclass) => superclass,
+                    _ => unreachable!("super is only defined as a class"),
+                };
+                // this is bound in the scope inside the one that defines super,
+                // in class methods it is the class, and super finds the class methods of the superclass
+                let this = Token { token_type: THIS, lexeme: intern("this"), ..keyword.clone() };
+                let object = self.environment.borrow().get_at(depth.get() - 1, &this)?;
+
+                let found = match object {
+                    Value::Class(_) => superclass.find_class_method(&method.lexeme),
+                    _ => superclass.find_method(&method.lexeme),
+                };
+                self.bind_method(&object, found, method, &method.lexeme)
+            }
+            Expr::This(keyword, depth) => {
+                self.environment.borrow().get_at(depth.get(), keyword)
+            }
+            Expr::Variable(name, depth) => {
+                self.environment.borrow().get_at(depth.get(), name)
+            }
+            Expr::Unary(operator, right) => {
+                let right = self.visit_expr(right)?;
+                if let (MINUS, Some(method)) = (operator.token_type, special_method(&right, "__neg__")) {
+                    return self.call(method, operator, Vec::new());
+                }
+                return match operator.token_type {
+                    MINUS => match right {
+                        Value::Numeric(number) => Ok(Value::Numeric(-number)),
+                        _ => Err(RuntimeError::new(operator, "Operand must be a number.")),
+                    },
+                    BANG => Ok(Value::Boolean(!is_truthy(&right))),
+                    TILDE => match right {
+                        Value::Numeric(number) => Ok(Value::Numeric(!integer(number, operator)? as f64)),
+                        _ => Err(RuntimeError::new(operator, "Operand must be a number.")),
+                    },
+                    _ => Err(RuntimeError::new(operator, "Unknown unary operator.")),
+                };
+            }
+        };
+    }
+}
+
+impl Interpreter {
+    /// runs the statements in the given scope and restores the current scope afterwards, also after an error
+    fn execute_block(&mut self, statements: &[Stmt], environment: Rc<RefCell<Environment>>) -> ExecResult {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = statements.iter().try_for_each(|statement| self.visit_stmt(statement));
+        self.environment = previous;
+        result
+    }
+
+    /// looks up a property of a value, the token is used to report errors
+    fn get(&mut self, object: &Value, token: &Token, name: &Symbol) -> EvalResult {
+        let property = match object {
+            Value::List(elements) => list::method(elements, name),
+            Value::Map(entries) => map::method(entries, name),
+            Value::Text(text) => strings::method(text, name),
+            Value::Iterator(iterator) => iterator::method(iterator, name),
+            Value::Error(error) => match name.as_str() {
+                "message" => Some(error.message.clone()),
+                "line" => Some(Value::Numeric(error.line as f64)),
+                _ => Option::None,
+            },
+            Value::Module(module) => module.globals.borrow().get_local(name),
+            // fields shadow methods
+            Value::Instance(instance) => match instance.field(name) {
+                Some(field) => Some(field),
+                None => return self.bind_method(object, instance.class.find_method(name), token, name),
+            },
+            Value::Class(class) => match class.field(name) {
+                Some(field) => Some(field),
+                None => return self.bind_method(object, class.find_class_method(name), token, name),
+            },
+            _ => {
+                let message = "Only instances, classes, lists, maps, strings, iterators, errors and modules have properties.";
+                return Err(RuntimeError::new(token, message));
+            }
+        };
+        property.ok_or_else(|| RuntimeError::new(token, &format!("Undefined property '{}'.", name)))
+    }
+
+    /// the methods that the class gets from its traits, leaving out those it defines itself,
+    /// two traits that define the same method are an error, the resolver reports that for the traits it knows
+    fn trait_methods(&mut self, class: &Class) -> Result<HashMap<Symbol, Rc<LoxFunction>>, RuntimeError> {
+        let mut methods: HashMap<Symbol, Rc<LoxFunction>> = HashMap::new();
+        let mut traits: HashMap<Symbol, Symbol> = HashMap::new();
+        for variable in &class.traits {
+            let name = variable.name();
+            let used = match self.visit_expr(variable)? {
+                Value::Trait(used) => used,
+                _ => return Err(RuntimeError::new(name, &format!("'{}' is not a trait.", name.lexeme))),
+            };
+
+            for (method, function) in &used.methods {
+                if class.methods.iter().any(|declaration| declaration.name.lexeme == *method) {
+                    continue;
+                }
+                // using the same trait twice is not a conflict
+                if let Some(existing) = methods.get(method) {
+                    if !Rc::ptr_eq(existing, function) {
+                        return Err(RuntimeError::new(name, &class::conflict(&traits[method], &used.name, method)));
+                    }
+                }
+                methods.insert(method.clone(), Rc::clone(function));
+                traits.insert(method.clone(), used.name.clone());
+            }
+        }
+        Ok(methods)
+    }
+
+    /// the method bound to the object, which is an instance or a class, a getter is called right away
+    fn bind_method(&mut self, object: &Value, method: Option<Rc<LoxFunction>>, token: &Token, name: &str) -> EvalResult {
+        let method = method.ok_or_else(|| RuntimeError::new(token, &format!("Undefined property '{}'.", name)))?;
+        let bound = Value::Function(method.bind(object.clone()));
+        if method.declaration.getter {
+            return self.call(bound, token, Vec::new());
+        }
+        Ok(bound)
+    }
+
+    /// runs the module at the given path the first time it is imported, after that it comes from the cache
+    fn import(&mut self, path: &Token) -> Result<Rc<Module>, RuntimeError> {
+        let name = match &path.literal {
+            Value::Text(name) => name.clone(),
+            _ => unreachable!("the parser only accepts strings as module paths"),
+        };
+
+        // relative paths start at the directory of the importing file
+        let directory = self.loading.last().and_then(|file| file.parent()).unwrap_or_else(|| Path::new(""));
+        // checked before the file is opened, so that failing to open it doesn't tell whether it exists
+        if !self.may_import(&module::normalize(&directory.join(name.as_str()))) {
+            return Err(RuntimeError::new(path, &format!("Can't import '{}' from outside the directory of the script.", name)));
+        }
+        let file = directory.join(name.as_str()).canonicalize()
+            .map_err(|error| RuntimeError::new(path, &format!("Can't open module '{}': {}.", name, error)))?;
+        // and again for links that point outside
+        if !self.may_import(&file) {
+            return Err(RuntimeError::new(path, &format!("Can't import '{}' from outside the directory of the script.", name)));
+        }
+
+        if let Some(module) = self.modules.get(&file) {
+            return Ok(Rc::clone(module));
+        }
+        if let Some(start) = self.loading.iter().position(|loading| *loading == file) {
+            let cycle: Vec<String> = self.loading[start..].iter().chain(std::iter::once(&file))
+                .map(|file| module::file_name(file)).collect();
+            return Err(RuntimeError::new(path, &format!("Import cycle: {}.", cycle.join(" -> "))));
+        }
+
+        let source = fs::read_to_string(&file)
+            .map_err(|error| RuntimeError::new(path, &format!("Can't read module '{}': {}.", name, error)))?;
+        // the errors themselves have already been reported
+        let statements = scan_tokens(&source).and_then(parse)
+            .and_then(|statements| resolve(&statements).map(|_| statements))
+            .map_err(|_| RuntimeError::new(path, &format!("Can't compile module '{}'.", name)))?;
+
+        let globals = Environment::new(Some(Rc::clone(&self.natives)));
+        self.loading.push(file.clone());
+        let result = self.execute_block(&statements, Rc::clone(&globals));
+        self.loading.pop();
+        if let Err(Unwind::Error(mut error)) = result {
+            error.calls.push((module::file_name(&file), path.line));
+            return Err(error);
+        }
+
+        let module = Rc::new(Module { path: file.clone(), globals });
+        self.modules.insert(file, Rc::clone(&module));
+        Ok(module)
+    }
+
+    /// without file access only files in the directory of the script or below it can be imported, and none in the prompt
+    fn may_import(&self, file: &Path) -> bool {
+        if self.file_access {
+            return true;
+        }
+        return match self.loading.first().and_then(|script| script.parent()) {
+            Some(root) => file.starts_with(root),
+            None => false,
+        };
+    }
+
+    /// the text that print shows for the value, also used for interpolated values and by str()
+    /// instances of classes with a toString method show what it returns, also inside lists and maps
+    pub fn stringify(&mut self, value: &Value) -> Result<String, RuntimeError> {
+        self.stringify_inside(value, &mut Vec::new())
+    }
+
+    /// open holds the lists and maps around the value, a list or map inside itself is shown as [...] or {...}
+    fn stringify_inside(&mut self, value: &Value, open: &mut Vec<*const ()>) -> Result<String, RuntimeError> {
+        return match value {
+            Value::Instance(instance) => match instance.class.find_method(&intern("toString")) {
+                Some(method) => {
+                    // errors are reported at the declaration, there is no call in the source to point to
+                    let name = method.declaration.name.clone();
+                    let method = Value::Function(method.bind(value.clone()));
+                    match self.call(method, &name, Vec::new())? {
+                        Value::Text(text) => Ok(text.to_string()),
+                        _ => Err(RuntimeError::new(&name, "toString() must return a string.")),
+                    }
+                }
+                None => Ok(value.to_string()),
+            },
+            Value::List(elements) => {
+                let pointer = Rc::as_ptr(elements) as *const ();
+                if open.contains(&pointer) {
+                    return Ok(String::from("[...]"));
+                }
+                open.push(pointer);
+                let elements = elements.borrow().clone();
+                let mut parts = Vec::new();
+                for element in &elements {
+                    parts.push(self.stringify_inside(element, open)?);
+                }
+                open.pop();
+                Ok(format!("[{}]", parts.join(", ")))
+            }
+            Value::Map(map) => {
+                let pointer = Rc::as_ptr(map) as *const ();
+                if open.contains(&pointer) {
+                    return Ok(String::from("{...}"));
+                }
+                open.push(pointer);
+                let entries = map.borrow().entries().to_vec();
+                let mut parts = Vec::new();
+                for (key, value) in &entries {
+                    parts.push(format!("{}: {}", self.stringify_inside(key, open)?, self.stringify_inside(value, open)?));
+                }
+                open.pop();
+                Ok(format!("{{{}}}", parts.join(", ")))
+            }
+            _ => Ok(value.to_string()),
+        };
+    }
+
+    /// calls the method with the given name without arguments
+    fn invoke(&mut self, object: &Value, token: &Token, name: &Symbol) -> EvalResult {
+        let method = self.get(object, token, name)?;
+        self.call(method, token, Vec::new())
+    }
+
+    /// applies a compound assignment (value is Some) or an increment (value is None) to the target,
+    /// evaluating the parts of the target only once, and returns the old and the new value
+    fn update(&mut self, target: &Expr, operator: &Token, value: Option<&Expr>) -> Result<(Value, Value), RuntimeError> {
+        // the arithmetic operator that the compound operator stands for
+        let token_type = match operator.token_type {
+            PLUSEQUAL | PLUSPLUS => PLUS,
+            MINUSEQUAL | MINUSMINUS => MINUS,
+            STAREQUAL => STAR,
+            _ => SLASH,
+        };
+        let arithmetic = Token { token_type, ..operator.clone() };
+
+        return match target {
+            Expr::Variable(name, depth) => {
+                let old = self.environment.borrow().get_at(depth.get(), name)?;
+                let operand = self.operand(value)?;
+                let new = self.operate(old.clone(), &arithmetic, operand)?;
+                self.environment.borrow_mut().assign_at(depth.get(), name, new.clone())?;
+                Ok((old, new))
+            }
+            Expr::Index(object, bracket, index) => {
+                let object = self.visit_expr(object)?;
+                let index = self.visit_expr(index)?;
+                let old = self.get_index(&object, bracket, index.clone())?;
+                let operand = self.operand(value)?;
+                let new = self.operate(old.clone(), &arithmetic, operand)?;
+                self.set_index(&object, bracket, index, new.clone())?;
+                Ok((old, new))
+            }
+            Expr::Get(object, name) => {
+                let object = self.visit_expr(object)?;
+                let old = self.get(&object, name, &name.lexeme)?;
+                let operand = self.operand(value)?;
+                let new = self.operate(old.clone(), &arithmetic, operand)?;
+                set(&object, name, new.clone())?;
+                Ok((old, new))
+            }
+            _ => Err(RuntimeError::new(operator, "Invalid assignment target.")),
+        };
+    }
+
+    /// the right operand of a compound assignment, which is 1 for an increment
+    fn operand(&mut self, value: Option<&Expr>) -> EvalResult {
+        return match value {
+            Some(value) => self.visit_expr(value),
+            None => Ok(Value::Numeric(1.0)),
+        };
+    }
+
+    fn call(&mut self, callee: Value, paren: &Token, arguments: Vec<Value>) -> EvalResult {
+        if let Some(method) = special_method(&callee, "__call__") {
+            return self.call(method, paren, arguments);
+        }
+
+        let (arity, variadic) = match &callee {
+            Value::Native(function) => (function.arity, function.variadic),
+            Value::Function(function) => (function.declaration.params.len(), false),
+            Value::Class(class) => (class.arity(), false),
+            _ => return Err(RuntimeError::new(paren, "Can only call functions and classes.")),
+        };
+        if variadic && arguments.len() < arity {
+            let message = format!("Expected at least {} arguments but got {}.", arity, arguments.len());
+            return Err(RuntimeError::new(paren, &message));
+        }
+        if !variadic && arguments.len() != arity {
+            let message = format!("Expected {} arguments but got {}.", arity, arguments.len());
+            return Err(RuntimeError::new(paren, &message));
+        }
+
+        return match callee {
+            Value::Native(function) => {
+                (function.function)(self, &arguments).map_err(|message| RuntimeError::new(paren, &message))
+            }
+            Value::Function(function) => {
+                if self.depth == MAX_CALL_DEPTH {
+                    return Err(RuntimeError::new(paren, "Stack overflow."));
+                }
+                self.depth += 1;
+                let result = self.call_function(&function, arguments);
+                self.depth -= 1;
+
+                // adds the function to the stack trace
+                result.map_err(|mut error| {
+                    error.calls.push((format!("{}()", function.declaration.name.lexeme), paren.line));
+                    error
+                })
+            }
+            Value::Class(class) => {
+                let instance = Value::Instance(LoxInstance::new(Rc::clone(&class)));
+                if let Some(initializer) = class.find_method(&intern("init")) {
+                    self.call(Value::Function(initializer.bind(instance.clone())), paren, arguments)?;
+                }
+                Ok(instance)
+            }
+            _ => unreachable!(),
+        };
+    }
+
+    /// applies a binary operator, instances can overload it with methods:
+    /// the arithmetic operators `+ - * / %` call `__add__ __sub__ __mul__ __div__ __mod__` on the left operand
+    /// with the right one, if the left operand has no such method the reflected `__radd__ __rsub__ __rmul__
+    /// __rdiv__ __rmod__` of the right operand is called with the left one, so `2 * v` calls `v.__rmul__(2)`
+    /// equality `== !=` calls `__eq__` on the left operand or else on the right one,
+    /// instances without it are compared by identity
+    /// the comparisons `< > <= >=` call `__lt__ __gt__ __le__ __ge__` on the left operand, or else the reflected
+    /// method of the right operand, which is `__gt__` for `<` and `__lt__` for `>`, so `2 < v` calls `v.__gt__(2)`,
+    /// without those `a <= b` is the negation of `a > b` and `a >= b` that of `a < b`,
+    /// and it is an error when no method is found
+    /// without a method the operator works as it does for other values, which for most is an error
+    fn operate(&mut self, left: Value, operator: &Token, right: Value) -> EvalResult {
+        if !matches!(left, Value::Instance(_)) && !matches!(right, Value::Instance(_)) {
+            return binary(left, operator, right);
+        }
+
+        return match operator.token_type {
+            EQUALEQUAL | BANGEQUAL => {
+                let equal = match (special_method(&left, "__eq__"), special_method(&right, "__eq__")) {
+                    (Some(method), _) => is_truthy(&self.call(method, operator, vec![right])?),
+                    (None, Some(method)) => is_truthy(&self.call(method, operator, vec![left])?),
+                    (None, None) => left == right,
+                };
+                Ok(Value::Boolean(equal == (operator.token_type == EQUALEQUAL)))
+            }
+            LESS | LESSEQUAL | GREATER | GREATEREQUAL => {
+                if let Some(result) = self.compare(&left, operator.token_type, &right, operator)? {
+                    return Ok(Value::Boolean(result));
+                }
+                let negation = match operator.token_type {
+                    LESSEQUAL => Some(GREATER),
+                    GREATEREQUAL => Some(LESS),
+                    _ => None,
+                };
+                if let Some(negation) = negation {
+                    if let Some(result) = self.compare(&left, negation, &right, operator)? {
+                        return Ok(Value::Boolean(!result));
+                    }
+                }
+                let (name, reflected) = operator_methods(operator.token_type).unwrap();
+                Err(RuntimeError::new(operator, &format!("Operands of '{}' need a {} method on the left or a {} method on the right.",
+                                                         operator.lexeme, name, reflected)))
+            }
+            token_type => match operator_methods(token_type) {
+                Some((name, reflected)) => {
+                    if let Some(method) = special_method(&left, name) {
+                        self.call(method, operator, vec![right])
+                    } else if let Some(method) = special_method(&right, reflected) {
+                        self.call(method, operator, vec![left])
+                    } else {
+                        binary(left, operator, right)
+                    }
+                }
+                None => binary(left, operator, right),
+            },
+        };
+    }
+
+    /// calls the method for the comparison on the left operand or the reflected one on the right operand,
+    /// None if neither has it
+    fn compare(&mut self, left: &Value, token_type: TokenType, right: &Value, operator: &Token) -> Result<Option<bool>, RuntimeError> {
+        let (name, reflected) = operator_methods(token_type).unwrap();
+        let (method, argument) = match (special_method(left, name), special_method(right, reflected)) {
+            (Some(method), _) => (method, right.clone()),
+            (None, Some(method)) => (method, left.clone()),
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(is_truthy(&self.call(method, operator, vec![argument])?)))
+    }
+
+    /// object[index], instances can overload it with __index__(index)
+    fn get_index(&mut self, object: &Value, bracket: &Token, index: Value) -> EvalResult {
+        if let Some(method) = special_method(object, "__index__") {
+            return self.call(method, bracket, vec![index]);
+        }
+        get_index(object, bracket, &index)
+    }
+
+    /// object[index] = value, instances can overload it with __setindex__(index, value)
+    fn set_index(&mut self, object: &Value, bracket: &Token, index: Value, value: Value) -> Result<(), RuntimeError> {
+        if let Some(method) = special_method(object, "__setindex__") {
+            self.call(method, bracket, vec![index, value])?;
+            return Ok(());
+        }
+        set_index(object, bracket, index, value)
+    }
+
+    /// runs the body in a new scope inside the closure, with the parameters bound to the arguments
+    fn call_function(&mut self, function: &LoxFunction, arguments: Vec<Value>) -> EvalResult {
+        let environment = Environment::new(Some(Rc::clone(&function.closure)));
+        for (param, argument) in function.declaration.params.iter().zip(arguments) {
+            environment.borrow_mut().define(param.lexeme.clone(), argument);
+        }
+
+        let result = self.execute_block(&function.declaration.body, environment);
+        if function.is_initializer {
+            if let Err(Unwind::Error(error)) = result {
+                return Err(error);
+            }
+            // the resolver doesn't allow returning a value from init, it returns the instance
+            return Ok(function.closure.borrow().get_local(&intern("this")).unwrap());
+        }
+
+        return match result {
+            Ok(_) => Ok(Value::None),
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(error)) => Err(error),
+            // the resolver doesn't allow break and continue to leave the function
+            Err(Unwind::Break) | Err(Unwind::Continue) => Ok(Value::None),
+        };
+    }
+}
+
+/// a method of a class or trait, init is an initializer unless it is a class method
+fn method(declaration: &Rc<Function>, closure: &Rc<RefCell<Environment>>, initializers: bool) -> Rc<LoxFunction> {
+    gc::manage(LoxFunction {
+        declaration: Rc::clone(declaration),
+        closure: Rc::clone(closure),
+        is_initializer: initializers && declaration.name.lexeme == "init",
+    })
+}
+
+/// the method of the class of an instance that overloads an operator, bound to the instance
+/// fields don't overload operators
+fn special_method(value: &Value, name: &str) -> Option<Value> {
+    return match value {
+        Value::Instance(instance) => instance.class.find_method(&intern(name))
+            .map(|method| Value::Function(method.bind(value.clone()))),
+        _ => None,
+    };
+}
+
+/// the method that overloads the arithmetic or comparison operator and the reflected one that is called on the right operand
+fn operator_methods(token_type: TokenType) -> Option<(&'static str, &'static str)> {
+    return match token_type {
+        PLUS => Some(("__add__", "__radd__")),
+        MINUS => Some(("__sub__", "__rsub__")),
+        STAR => Some(("__mul__", "__rmul__")),
+        SLASH => Some(("__div__", "__rdiv__")),
+        PERCENT => Some(("__mod__", "__rmod__")),
+        LESS => Some(("__lt__", "__gt__")),
+        GREATER => Some(("__gt__", "__lt__")),
+        LESSEQUAL => Some(("__le__", "__ge__")),
+        GREATEREQUAL => Some(("__ge__", "__le__")),
+        _ => None,
+    };
+}
+
+fn get_index(object: &Value, bracket: &Token, index: &Value) -> EvalResult {
+    let result = match object {
+        Value::List(elements) => list::get(&elements.borrow(), index),
+        Value::Map(entries) => map::get(&entries.borrow(), index),
+        Value::Text(text) => strings::get(text, index),
+        _ => Err(String::from("Only lists, maps and strings can be indexed.")),
+    };
+    result.map_err(|message| RuntimeError::new(bracket, &message))
+}
+
+/// assigns to a field, only instances and classes have fields
+fn set(object: &Value, name: &Token, value: Value) -> Result<(), RuntimeError> {
+    match object {
+        Value::Instance(instance) => instance.set(&name.lexeme, value),
+        Value::Class(class) => class.set(&name.lexeme, value),
+        _ => return Err(RuntimeError::new(name, "Only instances and classes have fields.")),
+    }
+    Ok(())
+}
+
+fn set_index(object: &Value, bracket: &Token, index: Value, value: Value) -> Result<(), RuntimeError> {
+    let result = match object {
+        Value::List(elements) => list::set(&mut elements.borrow_mut(), &index, value),
+        Value::Map(entries) => entries.borrow_mut().insert(index, value),
+        Value::Text(_) => Err(String::from("Strings are immutable.")),
+        _ => Err(String::from("Only lists, maps and strings can be indexed.")),
+    };
+    result.map_err(|message| RuntimeError::new(bracket, &message))
+}
+
+fn binary(left: Value, operator: &Token, right: Value) -> EvalResult {
+    return match operator.token_type {
+        PLUS => match (left, right) {
+            (Value::Numeric(left), Value::Numeric(right)) => Ok(Value::Numeric(left + right)),
+            (Value::Text(left), Value::Text(right)) => {
+                Ok(Value::Text(intern(&format!("{}{}", left, right))))
+            }
+            _ => Err(RuntimeError::new(operator, "Operands must be two numbers or two strings.")),
+        },
+        // the left operand of a comma is only evaluated for its side effects
+        COMMA => Ok(right),
+        EQUALEQUAL => Ok(Value::Boolean(left == right)),
+        BANGEQUAL => Ok(Value::Boolean(left != right)),
+        _ => {
+            let (left, right) = match (left, right) {
+                (Value::Numeric(left), Value::Numeric(right)) => (left, right),
+                _ => return Err(RuntimeError::new(operator, "Operands must be numbers.")),
+            };
+            match operator.token_type {
+                MINUS => Ok(Value::Numeric(left - right)),
+                STAR => Ok(Value::Numeric(left * right)),
+                SLASH => Ok(Value::Numeric(left / right)),
+                STARSTAR => Ok(Value::Numeric(left.powf(right))),
+                PERCENT | TILDESLASH if right == 0.0 => Err(RuntimeError::new(operator, "Division by zero.")),
+                PERCENT => Ok(Value::Numeric(left % right)),
+                TILDESLASH => Ok(Value::Numeric((left / right).floor())),
+                AMPERSAND => Ok(Value::Numeric((integer(left, operator)? & integer(right, operator)?) as f64)),
+                PIPE => Ok(Value::Numeric((integer(left, operator)? | integer(right, operator)?) as f64)),
+                CARET => Ok(Value::Numeric((integer(left, operator)? ^ integer(right, operator)?) as f64)),
+                LESSLESS | GREATERGREATER => {
+                    let (left, right) = (integer(left, operator)?, integer(right, operator)?);
+                    if !(0..64).contains(&right) {
+                        return Err(RuntimeError::new(operator, "Shift amount must be between 0 and 63."));
+                    }
+                    let shifted = if operator.token_type == LESSLESS { left << right } else { left >> right };
+                    Ok(Value::Numeric(shifted as f64))
+                }
+                GREATER => Ok(Value::Boolean(left > right)),
+                GREATEREQUAL => Ok(Value::Boolean(left >= right)),
+                LESS => Ok(Value::Boolean(left < right)),
+                LESSEQUAL => Ok(Value::Boolean(left <= right)),
+                _ => Err(RuntimeError::new(operator, "Unknown binary operator.")),
+            }
+        }
+    };
+}
+
+/// converts the operand of a bitwise operator to an integer,
+/// it is an error if the number has a fraction or doesn't fit in 64 bits
+fn integer(number: f64, operator: &Token) -> Result<i64, RuntimeError> {
+    if number.fract() == 0.0 && number >= i64::MIN as f64 && number < i64::MAX as f64 {
+        Ok(number as i64)
+    } else {
+        Err(RuntimeError::new(operator, "Operands of bitwise operators must be integers."))
+    }
+}
+
+/// returns whether the loop should go on after running its body,
+/// which is the case unless the body hit a break statement
+fn keep_looping(body: ExecResult) -> Result<bool, Unwind> {
+    return match body {
+        Ok(_) | Err(Unwind::Continue) => Ok(true),
+        Err(Unwind::Break) => Ok(false),
+        Err(error) => Err(error),
+    };
+}
+
+/// nil and false are falsey, everything else is truthy
+fn is_truthy(value: &Value) -> bool {
+    return match value {
+        Value::None => false,
+        Value::Boolean(value) => *value,
+        _ => true,
+    };
+}
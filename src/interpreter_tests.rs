@@ -0,0 +1,819 @@
+use std::{env, fs, process};
+use std::path::Path;
+
+use crate::interner::intern;
+use crate::interpreter::{Interpreter, RuntimeError};
+use crate::parser::parse;
+use crate::resolver::resolve;
+use crate::scanner::scan_tokens;
+use crate::tokens::{Token, Value};
+use crate::tokens::TokenType::IDENTIFIER;
+
+/// runs the program and returns the value of its global variable result
+fn run(source: &str) -> Result<Value, RuntimeError> {
+    run_with(source, Interpreter::new())
+}
+
+fn run_with(source: &str, mut interpreter: Interpreter) -> Result<Value, RuntimeError> {
+    let tokens = scan_tokens(source).unwrap();
+    let statements = parse(tokens).unwrap();
+    resolve(&statements).unwrap();
+
+    interpreter.interpret(&statements)?;
+
+    let result = Token { token_type: IDENTIFIER, lexeme: intern("result"), literal: Value::None, line: 1 };
+    let value = interpreter.globals.borrow().get(&result);
+    value
+}
+
+/// writes the files to a directory of their own and runs the first one as a script
+fn run_files(directory: &str, files: &[(&str, &str)]) -> Result<Value, RuntimeError> {
+    run_files_with(directory, files, Interpreter::for_script)
+}
+
+fn run_files_with(directory: &str, files: &[(&str, &str)], interpreter: fn(&Path) -> Interpreter)
+                  -> Result<Value, RuntimeError> {
+    let directory = env::temp_dir().join(format!("rust_lox_{}_{}", process::id(), directory));
+    for (path, source) in files {
+        let path = directory.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+
+    let script = directory.join(files[0].0);
+    let result = run_with(files[0].1, interpreter(&script));
+    fs::remove_dir_all(directory).unwrap();
+    result
+}
+
+fn evaluate(source: &str) -> Result<Value, RuntimeError> {
+    run(&format!("var result = {};", source))
+}
+
+fn evaluate_to_string(source: &str) -> String {
+    evaluate(source).unwrap().to_string()
+}
+
+fn runtime_error(source: &str) -> String {
+    evaluate(source).unwrap_err().message
+}
+
+fn run_to_string(source: &str) -> String {
+    run(source).unwrap().to_string()
+}
+
+#[test]
+fn test_arithmetic() {
+    assert_eq!(evaluate("1 + 2 * 3").unwrap(), Value::Numeric(7.0));
+    assert_eq!(evaluate_to_string("\"a\" + \"b\""), "ab");
+    assert_eq!(runtime_error("1 + true"), "Operands must be two numbers or two strings.");
+}
+
+#[test]
+fn test_list_literal_and_index() {
+    assert_eq!(evaluate_to_string("[1, 2, [3]]"), "[1, 2, [3]]");
+    assert_eq!(evaluate("[1, 2, 3][1]").unwrap(), Value::Numeric(2.0));
+    assert_eq!(evaluate("[1, 2, 3][1] = 5").unwrap(), Value::Numeric(5.0));
+}
+
+#[test]
+fn test_list_index_errors() {
+    assert_eq!(runtime_error("[1, 2, 3][3]"), "Index 3 out of range.");
+    assert_eq!(runtime_error("[1, 2, 3][-1]"), "Index -1 out of range.");
+    assert_eq!(runtime_error("[1][0.5]"), "Index must be an integer.");
+    assert_eq!(runtime_error("[][0] = 1"), "Index 0 out of range.");
+    assert_eq!(runtime_error("1[0]"), "Only lists, maps and strings can be indexed.");
+}
+
+#[test]
+fn test_list_slices() {
+    assert_eq!(evaluate_to_string("[1, 2, 3, 4][1:3]"), "[2, 3]");
+    assert_eq!(evaluate_to_string("[1, 2, 3, 4][:2]"), "[1, 2]");
+    assert_eq!(evaluate_to_string("[1, 2, 3, 4][2:]"), "[3, 4]");
+    assert_eq!(evaluate_to_string("[1, 2, 3, 4][:]"), "[1, 2, 3, 4]");
+    assert_eq!(runtime_error("[1, 2][0:3]"), "Index 3 out of range.");
+    assert_eq!(runtime_error("[1, 2][2:1]"), "Slice start 2 is after slice end 1.");
+}
+
+#[test]
+fn test_list_methods() {
+    assert_eq!(evaluate("[1, 2, 3].len()").unwrap(), Value::Numeric(3.0));
+    assert_eq!(evaluate("[1, 2, 3].pop()").unwrap(), Value::Numeric(3.0));
+    assert_eq!(evaluate("[1, 2, 3].remove(0)").unwrap(), Value::Numeric(1.0));
+    assert_eq!(evaluate("[1].push(2)").unwrap(), Value::None);
+    assert_eq!(evaluate("[1].insert(1, 2)").unwrap(), Value::None);
+    assert_eq!(runtime_error("[].pop()"), "Can't pop from an empty list.");
+    assert_eq!(runtime_error("[1].insert(2, 2)"), "Index 2 out of range.");
+    assert_eq!(runtime_error("[1].push()"), "Expected 1 arguments but got 0.");
+    assert_eq!(runtime_error("[1].size()"), "Undefined property 'size'.");
+}
+
+#[test]
+fn test_map_literal_and_index() {
+    assert_eq!(evaluate_to_string("{\"b\": 1, \"a\": 2, true: 3, 4: [5]}"), "{b: 1, a: 2, true: 3, 4: [5]}");
+    assert_eq!(evaluate("{\"a\": 1, \"b\": 2}[\"b\"]").unwrap(), Value::Numeric(2.0));
+    assert_eq!(evaluate("{0: 1}[-0]").unwrap(), Value::Numeric(1.0));
+    assert_eq!(evaluate("{\"a\": 1}[\"b\"] = 2").unwrap(), Value::Numeric(2.0));
+    assert_eq!(evaluate_to_string("{\"a\": 1, \"a\": 2}"), "{a: 2}");
+}
+
+#[test]
+fn test_map_errors() {
+    assert_eq!(runtime_error("{\"a\": 1}[\"b\"]"), "Undefined key 'b'.");
+    assert_eq!(runtime_error("{[]: 1}"), "Map keys must be strings, numbers or booleans.");
+    assert_eq!(runtime_error("{}[nil] = 1"), "Map keys must be strings, numbers or booleans.");
+}
+
+#[test]
+fn test_map_methods() {
+    assert_eq!(evaluate_to_string("{\"b\": 1, \"a\": 2, 3: 4}.keys()"), "[b, a, 3]");
+    assert_eq!(evaluate_to_string("{\"b\": 1, \"a\": 2, 3: 4}.values()"), "[1, 2, 4]");
+    assert_eq!(evaluate("{\"a\": 1}.has(\"a\")").unwrap(), Value::Boolean(true));
+    assert_eq!(evaluate("{\"a\": 1}.has(1)").unwrap(), Value::Boolean(false));
+    assert_eq!(evaluate("{\"a\": 1, \"b\": 2}.remove(\"a\")").unwrap(), Value::Numeric(1.0));
+    assert_eq!(evaluate("{\"a\": 1}.remove(\"b\")").unwrap(), Value::None);
+    assert_eq!(evaluate("{\"a\": 1, \"b\": 2}.len()").unwrap(), Value::Numeric(2.0));
+}
+
+#[test]
+fn test_variables_and_blocks() {
+    assert_eq!(run_to_string("var a = 1; var result = a + 1;"), "2");
+    assert_eq!(run_to_string("var result = 1; { var result = 2; } result = result + 1;"), "2");
+    assert_eq!(run("result = 1;").unwrap_err().message, "Undefined variable 'result'.");
+}
+
+#[test]
+fn test_lists_are_shared() {
+    assert_eq!(run_to_string("var result = [1]; var other = result; other.push(2); other[0] = 0;"), "[0, 2]");
+    assert_eq!(run_to_string("var result = [1, 2, 3]; result.insert(1, 4); result.remove(0);"), "[4, 2, 3]");
+}
+
+#[test]
+fn test_for_in_list() {
+    assert_eq!(run_to_string("var result = 0; for (x in [1, 2, 3]) result = result + x;"), "6");
+    assert_eq!(run_to_string("var result = []; for (x in [[1], [2]]) { var y = x[0]; result.push(y); }"), "[1, 2]");
+}
+
+#[test]
+fn test_for_in_map_string_and_range() {
+    assert_eq!(run_to_string("var result = []; for (k in {\"b\": 1, \"a\": 2}) result.push(k);"), "[b, a]");
+    assert_eq!(run_to_string("var result = \"\"; for (c in \"hello\") result = c + result;"), "olleh");
+    assert_eq!(run_to_string("var result = []; for (i in range(2, 5)) result.push(i);"), "[2, 3, 4]");
+}
+
+#[test]
+fn test_iterator_protocol() {
+    assert_eq!(run_to_string("var it = [1, 2].iterator(); var result = [it.next(), it.hasNext(), it.next(), it.hasNext()];"),
+               "[1, true, 2, false]");
+    assert_eq!(run_to_string("var result = []; var it = range(0, 3); it.next(); for (i in it) result.push(i);"), "[1, 2]");
+    assert_eq!(run_to_string("var it = [].iterator(); var result = it.next();"), "nil");
+    // nil elements of lists don't end the loop
+    assert_eq!(run_to_string("var result = []; for (x in [1, nil, 2]) result.push(x);"), "[1, nil, 2]");
+    assert_eq!(run("for (x in 1) print x;").unwrap_err().message,
+               "Only instances, classes, lists, maps, strings, iterators, errors and modules have properties.");
+}
+
+#[test]
+fn test_if_and_while() {
+    assert_eq!(run_to_string("var result = 1; if (result > 0) result = 2; else result = 3;"), "2");
+    assert_eq!(run_to_string("var result = 1; if (nil) result = 2; else result = 3;"), "3");
+    assert_eq!(run_to_string("var result = 0; while (result < 5) result = result + 2;"), "6");
+    // elements that are pushed while iterating are visited as well
+    assert_eq!(run_to_string("var result = [1, 2]; for (x in result) if (x < 3) result.push(x + 2);"), "[1, 2, 3, 4]");
+}
+
+#[test]
+fn test_for_loop() {
+    assert_eq!(run_to_string("var result = []; for (var i = 0; i < 3; i = i + 1) result.push(i);"), "[0, 1, 2]");
+    assert_eq!(run_to_string("var result = 0; for (; result < 3;) result = result + 1;"), "3");
+    // the loop variable is scoped to the loop
+    assert_eq!(run_to_string("var i = 10; for (var i = 0; i < 3; i = i + 1) {} var result = i;"), "10");
+}
+
+#[test]
+fn test_break_and_continue() {
+    assert_eq!(run_to_string("var result = 0; while (true) { result = result + 1; if (result == 3) break; }"), "3");
+    assert_eq!(run_to_string("var result = []; for (var i = 0; i < 5; i = i + 1) { if (i == 2) continue; result.push(i); }"),
+               "[0, 1, 3, 4]");
+    assert_eq!(run_to_string("var result = []; for (x in range(0, 10)) { if (x == 1) continue; if (x == 4) break; result.push(x); }"),
+               "[0, 2, 3]");
+    // break only leaves the innermost loop
+    assert_eq!(run_to_string("var result = 0; for (x in range(0, 3)) { while (true) break; result = result + 1; }"), "3");
+}
+
+#[test]
+fn test_conditional() {
+    assert_eq!(evaluate("true ? 1 : 2").unwrap(), Value::Numeric(1.0));
+    assert_eq!(evaluate("nil ? 1 : false ? 2 : 3").unwrap(), Value::Numeric(3.0));
+    // only the chosen branch is evaluated
+    assert_eq!(run_to_string("var result = 0; true ? result = 1 : (result = 2);"), "1");
+}
+
+#[test]
+fn test_logical_operators() {
+    assert_eq!(evaluate_to_string("[1 and 2, nil and 2, false or \"a\", 1 or 2, nil or false]"), "[2, nil, a, 1, false]");
+    // the right operand is only evaluated when the left one doesn't decide
+    assert_eq!(run_to_string("var result = 0; false and (result = 1); true or (result = 2);"), "0");
+    assert_eq!(run_to_string("var result = 0; if (result == 0 and !(result > 0)) result = 1;"), "1");
+}
+
+#[test]
+fn test_comma() {
+    assert_eq!(evaluate("(1, 2, 3)").unwrap(), Value::Numeric(3.0));
+    assert_eq!(run_to_string("var a = 0; var result = (a = 1, a + 1);"), "2");
+}
+
+#[test]
+fn test_modulo_exponent_and_integer_division() {
+    assert_eq!(evaluate("7 % 3").unwrap(), Value::Numeric(1.0));
+    assert_eq!(evaluate("-7 % 3").unwrap(), Value::Numeric(-1.0));
+    assert_eq!(evaluate("5.5 % 2").unwrap(), Value::Numeric(1.5));
+    assert_eq!(evaluate("-2 ** 2").unwrap(), Value::Numeric(-4.0));
+    assert_eq!(evaluate("2 ** 3 ** 2").unwrap(), Value::Numeric(512.0));
+    assert_eq!(evaluate("2 ** -1").unwrap(), Value::Numeric(0.5));
+    assert_eq!(evaluate("7 ~/ 2").unwrap(), Value::Numeric(3.0));
+    assert_eq!(evaluate("-7 ~/ 2").unwrap(), Value::Numeric(-4.0));
+    assert_eq!(runtime_error("1 ~/ 0"), "Division by zero.");
+    assert_eq!(runtime_error("1 % 0"), "Division by zero.");
+}
+
+#[test]
+fn test_comments_after_operands() {
+    let source = "var x = 3; var result = 0;\n\
+                  while (x > 0) // count down\n\
+                  { result += x; x -= 1; }\n\
+                  fun f() // note\n\
+                  { return result // the sum\n\
+                  ; }\n\
+                  result = f();";
+    assert_eq!(run(source).unwrap(), Value::Numeric(6.0));
+}
+
+#[test]
+fn test_bitwise_operators() {
+    assert_eq!(evaluate("6 & 3").unwrap(), Value::Numeric(2.0));
+    assert_eq!(evaluate("6 | 3").unwrap(), Value::Numeric(7.0));
+    assert_eq!(evaluate("6 ^ 3").unwrap(), Value::Numeric(5.0));
+    assert_eq!(evaluate("~5").unwrap(), Value::Numeric(-6.0));
+    assert_eq!(evaluate("1 << 4").unwrap(), Value::Numeric(16.0));
+    assert_eq!(evaluate("-16 >> 2").unwrap(), Value::Numeric(-4.0));
+    assert_eq!(runtime_error("1.5 & 1"), "Operands of bitwise operators must be integers.");
+    assert_eq!(runtime_error("~0.5"), "Operands of bitwise operators must be integers.");
+    assert_eq!(runtime_error("1 << 64"), "Shift amount must be between 0 and 63.");
+    assert_eq!(runtime_error("\"a\" | 1"), "Operands must be numbers.");
+}
+
+#[test]
+fn test_compound_assignment() {
+    assert_eq!(run_to_string("var result = 10; result += 5; result -= 3; result *= 2; result /= 4;"), "6");
+    assert_eq!(run_to_string("var result = \"a\"; result += \"b\";"), "ab");
+    assert_eq!(run_to_string("var a = 1; var result = (a += 2);"), "3");
+    assert_eq!(run_to_string("var result = [1, 2]; result[1] += 10;"), "[1, 12]");
+    assert_eq!(run_to_string("var result = {\"count\": 1}; result[\"count\"] *= 3;"), "{count: 3}");
+    assert_eq!(run("var a = nil; a += 1;").unwrap_err().message, "Operands must be two numbers or two strings.");
+    assert_eq!(run("b += 1;").unwrap_err().message, "Undefined variable 'b'.");
+}
+
+#[test]
+fn test_compound_assignment_evaluates_target_once() {
+    assert_eq!(run_to_string("var i = -1; var result = [0, 0]; result[i += 1] += 5;"), "[5, 0]");
+    assert_eq!(run_to_string("var calls = []; var xs = [[1]]; xs[(calls.push(1), 0)][0] += 1; var result = [xs, calls];"),
+               "[[[2]], [1]]");
+}
+
+#[test]
+fn test_increment_and_decrement() {
+    assert_eq!(run_to_string("var a = 1; var result = [a++, a, ++a, a];"), "[1, 2, 3, 3]");
+    assert_eq!(run_to_string("var a = 1; var result = [a--, a, --a, a];"), "[1, 0, -1, -1]");
+    assert_eq!(run_to_string("var i = 0; var result = [5, 6]; result[i++]++; ++result[i];"), "[6, 7]");
+    assert_eq!(run("var a = \"x\"; a++;").unwrap_err().message, "Operands must be two numbers or two strings.");
+}
+
+#[test]
+fn test_string_interpolation() {
+    assert_eq!(run_to_string("var name = \"Lox\"; var age = 41; var result = \"Hello ${name}, you are ${age + 1}\";"),
+               "Hello Lox, you are 42");
+    assert_eq!(run_to_string("var result = \"${[1, true, nil]} ${ {\"a\": \"${1 + 1}\"}[\"a\"] }\";"), "[1, true, nil] 2");
+    assert_eq!(run("var result = \"${missing}\";").unwrap_err().message, "Undefined variable 'missing'.");
+}
+
+#[test]
+fn test_functions() {
+    assert_eq!(run_to_string("fun add(a, b) { return a + b; } var result = add(1, 2);"), "3");
+    assert_eq!(run_to_string("fun f() {} var result = f();"), "nil");
+    assert_eq!(run_to_string("fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); } var result = fib(10);"),
+               "55");
+    assert_eq!(run_to_string("fun f() { for (x in [1, 2, 3]) if (x == 2) return x; } var result = f();"), "2");
+    assert_eq!(run("fun f(a) {} f();").unwrap_err().message, "Expected 1 arguments but got 0.");
+}
+
+#[test]
+fn test_closures() {
+    assert_eq!(run_to_string("fun counter() { var i = 0; fun count() { i += 1; return i; } return count; } \
+                              var c = counter(); c(); var result = [c(), counter()()];"), "[2, 1]");
+}
+
+#[test]
+fn test_closures_see_the_variables_declared_where_they_are_written() {
+    let source = "var a = \"global\"; var result = [];
+                  { fun showA() { result.push(a); } showA(); var a = \"block\"; showA(); }";
+    assert_eq!(run_to_string(source), "[global, global]");
+    let source = "var a = 1; var result = [];
+                  for (x in [1]) { var f = fun () { a = 2; return a; }; var a = 3; result.push(f()); result.push(a); }
+                  result.push(a);";
+    assert_eq!(run_to_string(source), "[2, 3, 2]");
+    let source = "class A { m() { return \"A\"; } } var result;
+                  class B < A { m() { var f = fun () { return super.m() + this.n(); }; return f(); } n() { return \"B\"; } }
+                  { var result = \"shadowed\"; } result = B().m();";
+    assert_eq!(run_to_string(source), "AB");
+}
+
+#[test]
+fn test_catch_thrown_values() {
+    assert_eq!(run_to_string("var result; try { throw \"boom\"; } catch (e) { result = [e.message, e.line]; }"),
+               "[boom, 1]");
+    assert_eq!(run_to_string("var result; try { throw [1]; } catch (e) { result = e.message; }"), "[1]");
+    assert_eq!(run_to_string("var result; try { try { throw 1; } catch (e) { throw e; } } catch (e) { result = e.message; }"),
+               "1");
+    assert_eq!(run_to_string("fun f() { throw \"deep\"; } var result; try { f(); } catch (e) { result = e.message; }"),
+               "deep");
+    assert_eq!(run("throw \"boom\";").unwrap_err().message, "boom");
+}
+
+#[test]
+fn test_catch_runtime_errors() {
+    assert_eq!(run_to_string("var result; try {\n 1 + nil;\n} catch (e) { result = [e.message, e.line]; }"),
+               "[Operands must be two numbers or two strings., 2]");
+    assert_eq!(run_to_string("var result; try { [][0]; } catch (e) { result = e.message; }"), "Index 0 out of range.");
+    assert_eq!(run("try { 1 - nil; } catch (e) { e.cause; }").unwrap_err().message, "Undefined property 'cause'.");
+}
+
+#[test]
+fn test_finally() {
+    assert_eq!(run_to_string("var result = []; try { result.push(1); } finally { result.push(2); }"), "[1, 2]");
+    assert_eq!(run_to_string("var result = []; try { throw 1; } catch (e) { result.push(e.message); } \
+                              finally { result.push(2); }"), "[1, 2]");
+    assert_eq!(run_to_string("var result = []; for (x in [1, 2]) try { break; } finally { result.push(x); }"), "[1]");
+    assert_eq!(run_to_string("var result = []; fun f() { try { return 1; } finally { result.push(2); } } \
+                              result.push(f());"), "[2, 1]");
+    assert_eq!(run("var result = []; try { throw 1; } finally { result.push(2); }").unwrap_err().message, "1");
+}
+
+#[test]
+fn test_stack_trace() {
+    let error = run("fun inner() {\n return 1 - \"a\";\n}\nfun outer() {\n inner();\n}\nouter();").unwrap_err();
+    assert_eq!(error.to_string(),
+               "Operands must be numbers.\n[line 2] in inner()\n[line 5] in outer()\n[line 7] in script");
+    assert_eq!(run("nil();").unwrap_err().to_string(), "Can only call functions and classes.\n[line 1] in script");
+}
+
+#[test]
+fn test_stack_overflow() {
+    // the interpreter needs more stack for a thousand calls than a test thread has
+    let child = std::thread::Builder::new().stack_size(256 * 1024 * 1024).spawn(|| {
+        run("fun f() { f(); } f();").unwrap_err().to_string()
+    });
+    assert_eq!(child.unwrap().join().unwrap(),
+               "Stack overflow.\n[line 1] in f() (repeated 999 more times)\n[line 1] in script");
+}
+
+#[test]
+fn test_import_module() {
+    let files = [
+        ("main.lox", "import \"lib/shapes.lox\" as shapes; var result = [shapes.area(2), shapes.name];"),
+        ("lib/shapes.lox", "var name = \"shapes\"; fun area(side) { return side * side; }"),
+    ];
+    assert_eq!(format!("{:?}", run_files("import", &files).unwrap()), "[4, shapes]");
+}
+
+#[test]
+fn test_from_import() {
+    let files = [
+        ("main.lox", "from \"lib/a.lox\" import a, twice; var result = [a, twice()];"),
+        // paths are relative to the importing file
+        ("lib/a.lox", "from \"b.lox\" import b; var a = \"a\"; fun twice() { return b + b; }"),
+        ("lib/b.lox", "var b = \"b\";"),
+    ];
+    assert_eq!(format!("{:?}", run_files("from_import", &files).unwrap()), "[a, bb]");
+
+    let files = [("main.lox", "from \"a.lox\" import range;"), ("a.lox", "")];
+    assert_eq!(run_files("from_import_missing", &files).unwrap_err().message, "Module 'a.lox' has no variable 'range'.");
+}
+
+#[test]
+fn test_modules_run_once_in_their_own_environment() {
+    let files = [
+        ("main.lox", "var result = []; import \"counter.lox\" as first; import \"counter.lox\" as second; \
+                      result.push(first == second); result.push(first.count);"),
+        ("counter.lox", "var result = \"not shared\"; var count = 0; count += 1;"),
+    ];
+    assert_eq!(format!("{:?}", run_files("cache", &files).unwrap()), "[true, 1]");
+}
+
+#[test]
+fn test_import_errors() {
+    let files = [("main.lox", "import \"a.lox\" as a;"), ("a.lox", "import \"b.lox\" as b;"),
+                 ("b.lox", "import \"a.lox\" as a;")];
+    assert_eq!(run_files("cycle", &files).unwrap_err().to_string(),
+               "Import cycle: a.lox -> b.lox -> a.lox.\n[line 1] in b.lox\n[line 1] in a.lox\n[line 1] in script");
+
+    let files = [("main.lox", "import \"missing.lox\" as m;")];
+    assert!(run_files("missing", &files).unwrap_err().message.starts_with("Can't open module 'missing.lox'"));
+}
+
+#[test]
+fn test_math_functions() {
+    assert_eq!(evaluate_to_string("[sqrt(16), pow(2, 10), floor(-1.5), ceil(1.2), round(2.5), abs(-3)]"),
+               "[4, 1024, -2, 2, 3, 3]");
+    assert_eq!(evaluate_to_string("[min(3, 1, 2), max(3), exp(0), log(E), cos(0), atan2(0, 1)]"), "[1, 3, 1, 1, 1, 0]");
+    assert_eq!(evaluate_to_string("[isNaN(sqrt(-1)), isNaN(1), INF > pow(10, 300), floor(PI)]"), "[true, false, true, 3]");
+}
+
+#[test]
+fn test_math_namespace() {
+    assert_eq!(evaluate_to_string("[math.sqrt(9), math.PI == PI, math.max(1, 2)]"), "[3, true, 2]");
+    assert_eq!(run_to_string("fun sqrt(x) { return x; } var result = [sqrt(4), math.sqrt(4)];"), "[4, 2]");
+}
+
+#[test]
+fn test_math_errors() {
+    assert_eq!(runtime_error("sqrt(\"4\")"), "Arguments of sqrt() must be numbers.");
+    assert_eq!(runtime_error("pow(2)"), "Expected 2 arguments but got 1.");
+    assert_eq!(runtime_error("min()"), "Expected at least 1 arguments but got 0.");
+    assert_eq!(runtime_error("math.max(1, nil)"), "Arguments of max() must be numbers.");
+    assert_eq!(run("var a = 1;\nsqrt(nil);").unwrap_err().token.line, 2);
+}
+
+#[test]
+fn test_string_methods() {
+    assert_eq!(evaluate_to_string("[\"héllo\".len(), \"héllo\".upper(), \"ÀB\".lower(), \"  x \".trim()]"),
+               "[5, HÉLLO, àb, x]");
+    assert_eq!(evaluate_to_string("[\"héllo\".substr(1, 3), \"héllo\".substr(3, 10), \"héllo\".indexOf(\"l\"), \"a\".indexOf(\"b\")]"),
+               "[éll, lo, 2, -1]");
+    assert_eq!(evaluate_to_string("[\"a,b,,c\".split(\",\"), \"aé\".split(\"\"), \"aXbX\".replace(\"X\", \"-\")]"),
+               "[[a, b, , c], [a, é], a-b-]");
+    assert_eq!(evaluate_to_string("[\"lox\".startsWith(\"lo\"), \"lox\".endsWith(\"lo\"), \"🌍x\".charAt(1), \"é\".ord()]"),
+               "[true, false, x, 233]");
+    assert_eq!(evaluate_to_string("[\"a\", \"b\"].join(\", \")"), "a, b");
+}
+
+#[test]
+fn test_string_functions() {
+    assert_eq!(evaluate_to_string("[len(\"日本語\"), len([1, 2]), len({\"a\": 1}), upper(\"a\"), chr(233), ord(\"日\")]"),
+               "[3, 2, 1, A, é, 26085]");
+    assert_eq!(evaluate_to_string("[substr(\"abc\", 1, 1), join([\"x\", \"y\"], \"\"), split(\"a b\", \" \")]"), "[b, xy, [a, b]]");
+}
+
+#[test]
+fn test_string_index_and_slice() {
+    assert_eq!(evaluate_to_string("[\"日本語\"[1], \"日本語\"[1:], \"日本語\"[:1], \"abc\"[1:1]]"), "[本, 本語, 日, ]");
+    assert_eq!(runtime_error("\"日本語\"[3]"), "Index 3 out of range.");
+    assert_eq!(runtime_error("\"abc\"[0] = \"x\""), "Strings are immutable.");
+}
+
+#[test]
+fn test_string_function_errors() {
+    assert_eq!(runtime_error("\"abc\".charAt(3)"), "Index 3 out of range.");
+    assert_eq!(runtime_error("\"abc\".substr(1, -1)"), "Length -1 is negative.");
+    assert_eq!(runtime_error("\"abc\".startsWith(1)"), "Argument of startsWith() must be a string.");
+    assert_eq!(runtime_error("upper(1)"), "Argument of upper() must be a string.");
+    assert_eq!(runtime_error("\"ab\".ord()"), "ord() needs a string of one character.");
+    assert_eq!(runtime_error("chr(-1)"), "Invalid character code -1.");
+    assert_eq!(runtime_error("[1].join(\"\")"), "join() can only join strings.");
+    assert_eq!(runtime_error("\"a\".trim(1)"), "Expected 0 arguments but got 1.");
+}
+
+#[test]
+fn test_file_functions() {
+    let directory = env::temp_dir().join(format!("rust_lox_{}_files", process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let source = format!("var dir = \"{}\"; var path = dir + \"/notes.txt\"; var result = [exists(path)]; \
+                          writeFile(path, \"one\"); appendFile(path, \" two\"); writeFile(dir + \"/a.txt\", \"\"); \
+                          result.push(readFile(path)); result.push(exists(path)); result.push(listDir(dir));",
+                         directory.display());
+    let result = run(&source);
+    fs::remove_dir_all(directory).unwrap();
+    assert_eq!(format!("{:?}", result.unwrap()), "[false, one two, true, [a.txt, notes.txt]]");
+}
+
+#[test]
+fn test_file_errors_are_catchable() {
+    assert!(runtime_error("readFile(\"/no/such/file\")").starts_with("Can't read '/no/such/file': "));
+    assert_eq!(run_to_string("var result; try { listDir(\"/no/such/dir\"); } catch (e) { result = e.line; }"), "1");
+    assert_eq!(runtime_error("writeFile(\"x\", 1)"), "Argument of writeFile() must be a string.");
+}
+
+#[test]
+fn test_deny_file_access() {
+    let mut interpreter = Interpreter::new();
+    interpreter.deny_file_access();
+    assert_eq!(run_with("exists(\"/\");", interpreter).unwrap_err().message, "File access is denied.");
+}
+
+#[test]
+fn test_deny_file_access_to_imports() {
+    let sandboxed = |script: &Path| {
+        let mut interpreter = Interpreter::for_script(script);
+        interpreter.deny_file_access();
+        interpreter
+    };
+    let files = [("script/main.lox", "import \"lib/a.lox\" as a; var result = a.a;"), ("script/lib/a.lox", "var a = 1;")];
+    assert_eq!(run_files_with("sandbox_inside", &files, sandboxed).unwrap(), Value::Numeric(1.0));
+
+    // files outside get the same error whether they exist or not
+    for module in ["../secret.lox", "lib/../../secret.lox", "../missing.lox", "/etc/passwd"] {
+        let source = format!("import \"{}\" as s;", module);
+        let files = [("script/main.lox", source.as_str()), ("secret.lox", "var leaked = 1;")];
+        assert_eq!(run_files_with("sandbox_outside", &files, sandboxed).unwrap_err().message,
+                   format!("Can't import '{}' from outside the directory of the script.", module));
+    }
+
+    let mut interpreter = Interpreter::new();
+    interpreter.deny_file_access();
+    assert_eq!(run_with("import \"a.lox\" as a;", interpreter).unwrap_err().message,
+               "Can't import 'a.lox' from outside the directory of the script.");
+}
+
+#[test]
+fn test_getenv() {
+    // reads PATH instead of setting a variable, which would race with the other tests reading the environment
+    let path = env::var("PATH").unwrap();
+    assert_eq!(evaluate_to_string("[getenv(\"PATH\"), getenv(\"RUST_LOX_NOT_SET\")]"), format!("[{}, nil]", path));
+}
+
+#[test]
+fn test_clock_and_time() {
+    assert_eq!(evaluate_to_string("[clock() > 1600000000, time() == floor(time()), sleep(1)]"), "[true, true, nil]");
+    assert_eq!(evaluate_to_string("formatTime(0, \"%Y-%m-%d %H:%M:%S\")"), "1970-01-01 00:00:00");
+    assert_eq!(evaluate_to_string("formatTime(951825599, \"%d/%m/%Y %H:%M:%S %%\")"), "29/02/2000 11:59:59 %");
+    assert_eq!(evaluate_to_string("formatTime(-1, \"%Y-%m-%d\")"), "1969-12-31");
+    assert_eq!(runtime_error("formatTime(0, \"%x\")"), "Unknown time format '%x'.");
+    assert_eq!(runtime_error("formatTime(pow(10, 300), \"%Y\")"), "formatTime() needs a time in seconds and a format string.");
+    assert_eq!(runtime_error("formatTime(0 / 0, \"%Y\")"), "formatTime() needs a time in seconds and a format string.");
+    assert_eq!(runtime_error("sleep(-1)"), "Argument of sleep() must be a number of milliseconds.");
+    assert_eq!(runtime_error("sleep(INF)"), "Argument of sleep() must be a number of milliseconds.");
+    assert_eq!(runtime_error("sleep(10 ** 300)"), "Argument of sleep() must be a number of milliseconds.");
+}
+
+#[test]
+fn test_random_is_reproducible() {
+    let source = "var result = [random(), randomInt(1, 6), randomInt(-3, -3)];";
+    assert_eq!(run_to_string(source), run_to_string(source));
+    assert_eq!(run_to_string("seed(7); var a = [random(), randomInt(0, 100)]; seed(7); \
+                              var b = [random(), randomInt(0, 100)]; var result = [a[0] == b[0], a[1] == b[1]];"),
+               "[true, true]");
+    assert_eq!(run_to_string("var result = true; for (i in range(0, 1000)) { var n = randomInt(1, 3); var r = random(); \
+                              \
+                              for (bad in [n < 1, n > 3, n != floor(n), r < 0, r >= 1]) if (bad) result = false; }"),
+               "true");
+    assert_eq!(runtime_error("randomInt(2, 1)"), "randomInt() range 2 to 1 is empty.");
+    assert_eq!(runtime_error("randomInt(pow(10, 300), pow(10, 300))"), "Arguments of randomInt() must be integers.");
+    assert_eq!(runtime_error("randomInt(-pow(2, 63), pow(2, 63))"), "Arguments of randomInt() must be integers.");
+    assert_eq!(runtime_error("seed(1 / 0)"), "Arguments of seed() must be integers.");
+}
+
+#[test]
+fn test_json_module() {
+    assert_eq!(evaluate_to_string("json.stringify({\"a\": [1, nil]})"), "{\"a\":[1,null]}");
+    assert_eq!(evaluate_to_string("json.parse(\"[1, 2]\")"), "[1, 2]");
+    assert_eq!(runtime_error("json.parse(\"[1,\")"), "Unexpected end of JSON at byte 3.");
+    assert_eq!(runtime_error("parse(\"1\")"), "Undefined variable 'parse'.");
+}
+
+#[test]
+fn test_classes() {
+    let counter = "class Counter { init(start) { this.count = start; } add(n) { this.count += n; return this; } }";
+    assert_eq!(run_to_string(&format!("{} var result = Counter(1).add(2).add(3).count;", counter)), "6");
+    assert_eq!(run_to_string(&format!("{} var c = Counter(0); c.count++; c.extra = [c.count]; var result = c.extra;", counter)), "[1]");
+    // a bound method remembers its instance, init returns it
+    assert_eq!(run_to_string(&format!("{} var c = Counter(5); var add = c.add; add(1); var result = [c.count, c.init(0) == c];", counter)),
+               "[6, true]");
+    assert_eq!(run("class A {} var result = A(1);").unwrap_err().message, "Expected 0 arguments but got 1.");
+    assert_eq!(run("class A {} var result = A().x;").unwrap_err().message, "Undefined property 'x'.");
+    assert_eq!(run("var x = 1; x.y = 2;").unwrap_err().message, "Only instances and classes have fields.");
+}
+
+#[test]
+fn test_inheritance() {
+    let source = "class A { name() { return \"A\"; } greet() { return \"I am \" + this.name(); } }
+                  class B < A { name() { return \"B of \" + super.name(); } }
+                  var result = B().greet();";
+    assert_eq!(run_to_string(source), "I am B of A");
+    assert_eq!(run("var A = 1; class B < A {}").unwrap_err().message, "Superclass must be a class.");
+    assert_eq!(run("class A {} class B < A { f() { return super.f; } } B().f();").unwrap_err().message,
+               "Undefined property 'f'.");
+}
+
+#[test]
+fn test_for_in_over_instances() {
+    let source = "class Countdown { init(n) { this.n = n; } iterator() { return this; }
+                      next() { if (this.n == 0) return nil; this.n -= 1; return this.n + 1; } }
+                  var result = []; for (i in Countdown(3)) result.push(i);";
+    assert_eq!(run_to_string(source), "[3, 2, 1]");
+    // iterator() can return the iterator of a list
+    let source = "class Bag { init() { this.items = [1, 2]; } iterator() { return this.items.iterator(); } }
+                  var result = []; for (i in Bag()) result.push(i);";
+    assert_eq!(run_to_string(source), "[1, 2]");
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(evaluate_to_string("[nil, true, 1, -0, 2.5, 1 / 3, 10000000, 0.0001, 1 / 0, -1 / 0, 0 / 0]"),
+               "[nil, true, 1, -0, 2.5, 0.3333333333333333, 1.0E7, 1.0E-4, Infinity, -Infinity, NaN]");
+    assert_eq!(evaluate_to_string("[2 ** 70, 123456789 * 1000, 0.001]"), "[1.1805916207174113E21, 1.23456789E11, 0.001]");
+    assert_eq!(run_to_string("fun f() {} class A {} var result = [f, clock, A, A()];"), "[<fn f>, <native fn>, A, A instance]");
+}
+
+#[test]
+fn test_display_lists_and_maps_inside_themselves() {
+    let source = "var xs = [1]; xs.push(xs); var m = {\"xs\": xs}; m[\"m\"] = m; xs.push(m);";
+    assert_eq!(run_to_string(&format!("{} var result = xs;", source)), "[1, [...], {xs: [...], m: {...}}]");
+    assert_eq!(run_to_string(&format!("{} var result = str(m);", source)), "{xs: [1, [...], {...}], m: {...}}");
+    // the same list twice next to each other is not a cycle
+    assert_eq!(run_to_string("var xs = [1]; var result = str([xs, xs]);"), "[[1], [1]]");
+}
+
+#[test]
+fn test_to_string() {
+    let point = "class Point { init(x, y) { this.x = x; this.y = y; } toString() { return \"(${this.x}, ${this.y})\"; } }";
+    assert_eq!(run_to_string(&format!("{} var result = \"at ${{Point(1, 2)}}\";", point)), "at (1, 2)");
+    assert_eq!(run_to_string(&format!("{} var result = str([Point(1, 2), {{\"p\": Point(3, 4)}}]);", point)),
+               "[(1, 2), {p: (3, 4)}]");
+    assert_eq!(run("class A { toString() { return 1; } } print A();").unwrap_err().message,
+               "toString() must return a string.");
+}
+
+#[test]
+fn test_type_str_and_num() {
+    assert_eq!(run_to_string("class A {} fun f() {}
+                              var result = [type(nil), type(true), type(1), type(\"a\"), type([]), type({}),
+                                            type(f), type(clock), type(A), type(A()), type(math)];"),
+               "[nil, boolean, number, string, list, map, function, function, class, A, module]");
+    assert_eq!(evaluate("str(1) + str(nil) + str(2.5)").unwrap(), Value::Text(intern("1nil2.5")));
+    assert_eq!(evaluate("[num(\"3.5\"), num(\" -12 \"), num(7)]").unwrap().to_string(), "[3.5, -12, 7]");
+    assert_eq!(runtime_error("num(\"abc\")"), "Can't convert 'abc' to a number.");
+    assert_eq!(runtime_error("num(\"1e5\")"), "Can't convert '1e5' to a number.");
+    assert_eq!(runtime_error("num(\"1.\")"), "Can't convert '1.' to a number.");
+    assert_eq!(runtime_error("num(true)"), "Argument of num() must be a number or a string.");
+}
+
+#[test]
+fn test_json_stringify_instances() {
+    let source = "class P { init() { this.b = 1; this.a = [true]; } } var result = json.stringify(P());";
+    assert_eq!(run_to_string(source), "{\"b\":1,\"a\":[true]}");
+    assert_eq!(run("class P {} var p = P(); p.self = p; json.stringify(p);").unwrap_err().message,
+               "Can't convert a list, map or instance that contains itself to JSON.");
+}
+
+const VECTOR: &str = "class Vector {
+    init(x, y) { this.x = x; this.y = y; }
+    __add__(other) { return Vector(this.x + other.x, this.y + other.y); }
+    __sub__(other) { return Vector(this.x - other.x, this.y - other.y); }
+    __mul__(factor) { return Vector(this.x * factor, this.y * factor); }
+    __rmul__(factor) { return this * factor; }
+    __neg__() { return Vector(-this.x, -this.y); }
+    __eq__(other) { return type(other) == \"Vector\" and this.x == other.x and this.y == other.y; }
+    __lt__(other) { return this.x * this.x + this.y * this.y < other.x * other.x + other.y * other.y; }
+    __index__(i) { return i == 0 ? this.x : this.y; }
+    __setindex__(i, value) { if (i == 0) this.x = value; else this.y = value; }
+    toString() { return \"(${this.x}, ${this.y})\"; }
+}";
+
+fn run_vector(source: &str) -> Result<Value, RuntimeError> {
+    run(&format!("{} {}", VECTOR, source))
+}
+
+#[test]
+fn test_arithmetic_operator_overloading() {
+    let result = run_vector("var v = Vector(1, 2); var result = str([v + v, v - Vector(1, 1), v * 3, 2 * v, -v]);");
+    assert_eq!(result.unwrap().to_string(), "[(2, 4), (0, 1), (3, 6), (2, 4), (-1, -2)]");
+    let result = run_vector("var v = Vector(1, 2); v += Vector(1, 1); v *= 2; var result = str(v);");
+    assert_eq!(result.unwrap().to_string(), "(4, 6)");
+    // without __rsub__ the left operand decides, and numbers can't be subtracted from instances
+    assert_eq!(run_vector("var result = 1 - Vector(1, 2);").unwrap_err().message, "Operands must be numbers.");
+    assert_eq!(run_vector("var result = Vector(1, 2) / 2;").unwrap_err().message, "Operands must be numbers.");
+}
+
+#[test]
+fn test_comparison_operator_overloading() {
+    let result = run_vector("var a = Vector(1, 1); var b = Vector(2, 2);
+                             var result = [a == Vector(1, 1), a != b, 1 == a, a == 1, a < b, a > b, a <= a, b >= a];");
+    assert_eq!(result.unwrap().to_string(), "[true, true, false, false, true, false, true, true]");
+    assert_eq!(run_vector("var result = Vector(3, 4) > 2;").unwrap_err().message,
+               "Operands of '>' need a __gt__ method on the left or a __lt__ method on the right.");
+    assert_eq!(run("class A {} var result = 2 <= A();").unwrap_err().message,
+               "Operands of '<=' need a __le__ method on the left or a __ge__ method on the right.");
+
+    // the left operand decides, then the reflected method of the right operand, <= and >= are derived from > and <
+    let source = "class Length { init(n) { this.n = n; }
+                      __lt__(other) { return this.n < other; } __gt__(other) { return this.n > other; } }
+                  var l = Length(3); var result = [l < 4, l > 2, 4 > l, 2 < l, l <= 3, l >= 4, 3 <= l, 4 >= l];";
+    assert_eq!(run_to_string(source), "[true, true, true, true, true, false, true, true]");
+    let source = "class Length { init(n) { this.n = n; } __le__(other) { return \"le\"; } }
+                  var result = [Length(3) <= 1, 1 >= Length(3)];";
+    assert_eq!(run_to_string(source), "[true, true]");
+    // instances without __eq__ are equal only to themselves
+    assert_eq!(run_to_string("class A {} var a = A(); var result = [a == a, a == A()];"), "[true, false]");
+}
+
+#[test]
+fn test_index_and_call_overloading() {
+    let result = run_vector("var v = Vector(1, 2); v[0] = 5; v[1] += 1; var result = [v[0], v[1]];");
+    assert_eq!(result.unwrap().to_string(), "[5, 3]");
+    let source = "class Adder { init(n) { this.n = n; } __call__(x) { return x + this.n; } }
+                  fun twice(f, x) { return f(f(x)); }
+                  var add = Adder(2); var result = [add(1), twice(add, 1)];";
+    assert_eq!(run_to_string(source), "[3, 5]");
+    assert_eq!(run("class A {} A()();").unwrap_err().message, "Can only call functions and classes.");
+    assert_eq!(run("class A {} A()[0];").unwrap_err().message, "Only lists, maps and strings can be indexed.");
+}
+
+#[test]
+fn test_class_methods_and_fields() {
+    let source = "class Counter {
+                      class var count = 0;
+                      class var origin;
+                      init() { Counter.count += 1; }
+                      class created() { return this.count; }
+                  }
+                  Counter(); Counter();
+                  var result = [Counter.created(), Counter.count, Counter.origin];";
+    assert_eq!(run_to_string(source), "[2, 2, nil]");
+    // subclasses inherit the methods and fields of the class, assigning a field sets it on the class it is assigned on
+    let source = "class A { class var name = \"a\"; class describe() { return \"class \" + this.name; } }
+                  class B < A { class describe() { return super.describe() + \"!\"; } }
+                  B.name = \"b\";
+                  var result = [A.describe(), B.describe(), A.name];";
+    assert_eq!(run_to_string(source), "[class a, class b!, a]");
+    assert_eq!(run("class A { f() {} } A.f();").unwrap_err().message, "Undefined property 'f'.");
+    assert_eq!(run("class A { class f() {} } A().f();").unwrap_err().message, "Undefined property 'f'.");
+}
+
+#[test]
+fn test_getters() {
+    let source = "class Rectangle {
+                      init(w, h) { this.w = w; this.h = h; }
+                      area { return this.w * this.h; }
+                  }
+                  class Square < Rectangle {
+                      init(side) { super.init(side, side); }
+                      area { return super.area + 0.5; }
+                      class unit { return Square(1); }
+                  }
+                  var r = Rectangle(2, 3);
+                  var result = [r.area, Square(2).area, Square.unit.area];";
+    assert_eq!(run_to_string(source), "[6, 4.5, 1.5]");
+    // a getter runs on every read, a field with the same name shadows it
+    assert_eq!(run_to_string("class A { next { this.n = this.n + 1; return this.n; } }
+                              var a = A(); a.n = 0; a.next; var result = [a.next, a.n];"), "[2, 2]");
+    assert_eq!(run_to_string("class A { x { return 1; } } var a = A(); a.x += 1; var result = a.x;"), "2");
+}
+
+#[test]
+fn test_traits() {
+    let source = "trait Named { name { return this.first + \" \" + this.last; } greet() { return \"hi \" + this.name; } }
+                  trait Aged { older() { this.age += 1; return this; } describe() { return \"aged\"; } }
+                  class Base { describe() { return \"base\"; } kind() { return \"base\"; } }
+                  class Person < Base with Named, Aged {
+                      init(first, last) { this.first = first; this.last = last; this.age = 40; }
+                      kind() { return \"person\"; }
+                  }
+                  var p = Person(\"Ada\", \"Lovelace\").older();
+                  var result = [p.greet(), p.age, p.describe(), p.kind(), type(Named)];";
+    // the methods of the class come first, then those of the traits and then those of the superclass
+    assert_eq!(run_to_string(source), "[hi Ada Lovelace, 41, aged, person, trait]");
+    assert_eq!(run("var T = 1; class A with T {}").unwrap_err().message, "'T' is not a trait.");
+}
+
+#[test]
+fn test_trait_conflicts_from_modules() {
+    // the resolver doesn't see the traits of other files, so the conflict is found when the class is declared
+    let files = [
+        ("main.lox", "from \"traits.lox\" import A, B; class C with A, B {}"),
+        ("traits.lox", "trait A { f() {} } trait B { f() {} }"),
+    ];
+    assert_eq!(run_files("trait_conflicts", &files).unwrap_err().message,
+               "Traits A and B both define 'f', the class must override it.");
+    let files = [
+        ("main.lox", "from \"traits.lox\" import A, B; class C with A, B { f() { return 1; } } var result = C().f();"),
+        ("traits.lox", "trait A { f() {} } trait B { f() {} }"),
+    ];
+    assert_eq!(run_files("trait_overrides", &files).unwrap(), Value::Numeric(1.0));
+}
+
+#[test]
+fn test_lambdas() {
+    let source = "fun apply(f, x) { return f(x); }
+                  var offset = 10;
+                  var add = fun (a, b) { return a + b + offset; };
+                  var result = [add(1, 2), apply((x) => x * 2, 4), apply(fun (x) { return -x; }, 3), (() => offset)()];";
+    assert_eq!(run_to_string(source), "[13, 8, -3, 10]");
+    // lambdas are closures, also over this
+    let source = "fun counter() { var n = 0; return () => n += 1; }
+                  class Box { init(v) { this.v = v; } getter() { return () => this.v; } }
+                  var c = counter(); c(); fun (x) { offset = x; }(5);
+                  var result = [c(), Box(7).getter()(), offset, (a) => a];";
+    assert_eq!(run_to_string(&format!("var offset = 0; {}", source)), "[2, 7, 5, <fn lambda>]");
+    assert_eq!(run("var f = (a) => a + nil;\nf(1);").unwrap_err().to_string(),
+               "Operands must be two numbers or two strings.\n[line 1] in lambda()\n[line 2] in script");
+}
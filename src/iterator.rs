@@ -0,0 +1,95 @@
+use std::cell::RefCell;
+use std::iter::Peekable;
+use std::rc::Rc;
+
+use crate::interner::{intern, Symbol};
+use crate::map::Map;
+use crate::native::NativeFunction;
+use crate::tokens::Value;
+
+/// the iterator protocol:
+/// an iterable value has an iterator() method that returns an iterator,
+/// an iterator has a next() method that returns the next element, or nil when there are no more.
+/// lists, maps (their keys), strings (their characters) and ranges are iterable.
+/// their iterators also have a hasNext() method, because a list can contain nil,
+/// and for-in reads them directly, so it doesn't stop at a nil element.
+/// iterators are iterable themselves, iterator() returns the same iterator.
+/// for-in only calls these methods, so any value that has them can be iterated.
+pub struct LoxIterator {
+    elements: RefCell<Peekable<Box<dyn Iterator<Item=Value>>>>,
+}
+
+impl LoxIterator {
+    pub fn next(&self) -> Option<Value> {
+        self.elements.borrow_mut().next()
+    }
+}
+
+/// wraps the rust iterator in a lox iterator value
+fn iterate(elements: impl Iterator<Item=Value> + 'static) -> Value {
+    let elements: Box<dyn Iterator<Item=Value>> = Box::new(elements);
+    Value::Iterator(Rc::new(LoxIterator { elements: RefCell::new(elements.peekable()) }))
+}
+
+/// returns the method with the given name bound to the iterator,
+/// or None if iterators don't have a method with that name
+pub fn method(iterator: &Rc<LoxIterator>, name: &str) -> Option<Value> {
+    let iterator = Rc::clone(iterator);
+    let method = match name {
+        "iterator" => NativeFunction::new("iterator", 0, move |_, _| {
+            Ok(Value::Iterator(Rc::clone(&iterator)))
+        }),
+        "hasNext" => NativeFunction::new("hasNext", 0, move |_, _| {
+            Ok(Value::Boolean(iterator.elements.borrow_mut().peek().is_some()))
+        }),
+        "next" => NativeFunction::new("next", 0, move |_, _| {
+            Ok(iterator.next().unwrap_or(Value::None))
+        }),
+        _ => return None,
+    };
+    Some(Value::Native(Rc::new(method)))
+}
+
+/// iterates over the list as it is at the time next() is called,
+/// so elements pushed while iterating are visited as well
+pub fn of_list(list: &Rc<RefCell<Vec<Value>>>) -> Value {
+    let list = Rc::clone(list);
+    let mut index = 0;
+    iterate(std::iter::from_fn(move || {
+        let element = list.borrow().get(index).cloned();
+        index += 1;
+        element
+    }))
+}
+
+/// iterates over the keys the map had when the iterator was created, in insertion order
+pub fn of_map(map: &Map) -> Value {
+    let keys: Vec<Value> = map.entries().iter().map(|(key, _)| key.clone()).collect();
+    iterate(keys.into_iter())
+}
+
+/// iterates over the characters of the text, every character is a string of its own
+pub fn of_text(text: &Symbol) -> Value {
+    let characters: Vec<Value> = text.chars().map(|character| Value::Text(intern(&character.to_string()))).collect();
+    iterate(characters.into_iter())
+}
+
+/// native function range(start, end) that iterates over start, start + 1, ... up to but not including end
+pub fn range() -> NativeFunction {
+    NativeFunction::new("range", 2, |_, arguments| {
+        return match (&arguments[0], &arguments[1]) {
+            (Value::Numeric(start), Value::Numeric(end)) => {
+                let (mut current, end) = (*start, *end);
+                Ok(iterate(std::iter::from_fn(move || {
+                    if current < end {
+                        current += 1.0;
+                        Some(Value::Numeric(current - 1.0))
+                    } else {
+                        None
+                    }
+                })))
+            }
+            _ => Err(String::from("Range bounds must be numbers.")),
+        };
+    })
+}
@@ -0,0 +1,389 @@
+use std::rc::Rc;
+
+use crate::interner::intern;
+use crate::map::Map;
+use crate::native::NativeFunction;
+use crate::tokens::Value;
+
+/// arrays and objects nested deeper than this are rejected, instead of overflowing the stack
+const MAX_DEPTH: usize = 512;
+
+/// larger indents are cut down to this, like JSON.stringify in javascript does
+const MAX_INDENT: f64 = 10.0;
+
+/// the functions of the json module
+/// null, booleans, numbers and strings map to nil, booleans, numbers and strings,
+/// arrays map to lists and objects to maps with string keys
+/// stringify also writes instances as objects with their fields
+pub fn natives() -> Vec<(&'static str, Value)> {
+    let parse = NativeFunction::new("parse", 1, |_, arguments| {
+        return match &arguments[0] {
+            Value::Text(text) => Parser { text: text.as_bytes(), position: 0, depth: 0 }.parse(),
+            _ => Err(String::from("Argument of parse() must be a string.")),
+        };
+    });
+    // the indent is optional, without it or with 0 the output is on one line, and it is at most MAX_INDENT
+    let stringify = NativeFunction::variadic("stringify", 1, |_, arguments| {
+        let indent = match arguments.get(1) {
+            None => 0,
+            Some(Value::Numeric(indent)) if indent.fract() == 0.0 && *indent >= 0.0 => indent.min(MAX_INDENT) as usize,
+            Some(_) => return Err(String::from("Indent must be a non-negative integer.")),
+        };
+        if arguments.len() > 2 {
+            return Err(format!("Expected at most 2 arguments but got {}.", arguments.len()));
+        }
+
+        let mut writer = Writer { indent, output: String::new(), open: Vec::new() };
+        writer.write(&arguments[0])?;
+        Ok(Value::Text(intern(&writer.output)))
+    });
+    vec![("parse", Value::native(parse)), ("stringify", Value::native(stringify))]
+}
+
+/// recursive descent parser over the bytes of the text, positions in errors are byte offsets
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    /// the text must contain one value, with nothing but whitespace around it
+    fn parse(&mut self) -> Result<Value, String> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        if self.position < self.text.len() {
+            return Err(self.unexpected());
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        return match self.peek() {
+            Some(b'{') => self.nested(Parser::object),
+            Some(b'[') => self.nested(Parser::array),
+            Some(b'"') => Ok(Value::Text(intern(&self.string()?))),
+            Some(b't') => self.literal("true", Value::Boolean(true)),
+            Some(b'f') => self.literal("false", Value::Boolean(false)),
+            Some(b'n') => self.literal("null", Value::None),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            _ => Err(self.unexpected()),
+        };
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Value, String>) -> Result<Value, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!("JSON is nested too deeply at byte {}.", self.position));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.position += 1;
+        let mut map = Map::default();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Value::map(map));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.unexpected());
+            }
+            let key = Value::Text(intern(&self.string()?));
+            self.skip_whitespace();
+            self.expect(b':')?;
+            let value = self.value()?;
+            map.insert(key, value)?;
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Value::map(map));
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.position += 1;
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Value::list(elements));
+        }
+        loop {
+            elements.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Value::list(elements));
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    /// the contents of a string, the position is at the opening quote
+    fn string(&mut self) -> Result<String, String> {
+        self.position += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.position += 1;
+                    // the text was a valid string and the escapes add valid characters
+                    return Ok(String::from_utf8(bytes).unwrap());
+                }
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.unexpected()),
+                    };
+                    self.position += 1;
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                Some(byte) if byte < 0x20 => return Err(self.unexpected()),
+                Some(byte) => {
+                    bytes.push(byte);
+                    self.position += 1;
+                }
+                None => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    /// the character of \uXXXX, or of a surrogate pair \uXXXX\uXXXX
+    /// the position is at the u and ends at the last hex digit
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let start = self.position - 1;
+        let high = self.hex()?;
+        let code = if (0xD800..0xDC00).contains(&high) && self.text[self.position + 1..].starts_with(b"\\u") {
+            self.position += 2;
+            let low = self.hex()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(format!("Invalid unicode escape at byte {}.", start));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| format!("Invalid unicode escape at byte {}.", start))
+    }
+
+    /// the four hex digits after the u of an escape
+    fn hex(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            self.position += 1;
+            let digit = self.peek().and_then(|byte| (byte as char).to_digit(16)).ok_or_else(|| self.unexpected())?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    /// -?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.position;
+        self.skip(b'-');
+        if !self.skip(b'0') {
+            self.digits()?;
+        }
+        if self.skip(b'.') {
+            self.digits()?;
+        }
+        if self.skip(b'e') || self.skip(b'E') {
+            if !self.skip(b'+') {
+                self.skip(b'-');
+            }
+            self.digits()?;
+        }
+        // the bytes are ascii, and rust accepts every number json does
+        let text = std::str::from_utf8(&self.text[start..self.position]).unwrap();
+        Ok(Value::Numeric(text.parse().unwrap()))
+    }
+
+    /// one or more digits
+    fn digits(&mut self) -> Result<(), String> {
+        if !matches!(self.peek(), Some(b'0'..=b'9')) {
+            return Err(self.unexpected());
+        }
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.position += 1;
+        }
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        for byte in word.bytes() {
+            self.expect(byte)?;
+        }
+        Ok(value)
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.skip(byte) { Ok(()) } else { Err(self.unexpected()) }
+    }
+
+    /// advances if the next byte is the given one
+    fn skip(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.position).copied()
+    }
+
+    /// the error for the byte at the current position
+    fn unexpected(&self) -> String {
+        return match self.peek() {
+            // a multibyte character is shown as its first byte, which is enough to find it
+            Some(byte) if byte.is_ascii_graphic() => {
+                format!("Unexpected '{}' in JSON at byte {}.", byte as char, self.position)
+            }
+            Some(byte) => format!("Unexpected byte 0x{:02x} in JSON at byte {}.", byte, self.position),
+            None => format!("Unexpected end of JSON at byte {}.", self.position),
+        };
+    }
+}
+
+/// writes values as json, with the given number of spaces per level or on one line if it is 0
+struct Writer {
+    indent: usize,
+    output: String,
+    // the lists, maps and instances that are being written, to detect cycles
+    open: Vec<*const ()>,
+}
+
+impl Writer {
+    fn write(&mut self, value: &Value) -> Result<(), String> {
+        match value {
+            Value::None => self.output.push_str("null"),
+            Value::Boolean(value) => self.output.push_str(&value.to_string()),
+            Value::Numeric(number) if number.is_finite() => self.output.push_str(&number.to_string()),
+            Value::Numeric(number) => return Err(format!("Can't convert {} to JSON.", number)),
+            Value::Text(text) => self.string(text),
+            Value::List(elements) => {
+                self.enter(Rc::as_ptr(elements) as *const ())?;
+                self.output.push('[');
+                for (index, element) in elements.borrow().iter().enumerate() {
+                    self.separator(index)?;
+                    self.write(element)?;
+                }
+                self.close(elements.borrow().is_empty(), ']');
+            }
+            Value::Map(map) => {
+                self.enter(Rc::as_ptr(map) as *const ())?;
+                self.output.push('{');
+                for (index, (key, value)) in map.borrow().entries().iter().enumerate() {
+                    self.separator(index)?;
+                    self.entry(key, value)?;
+                }
+                self.close(map.borrow().entries().is_empty(), '}');
+            }
+            Value::Instance(instance) => {
+                self.enter(Rc::as_ptr(instance) as *const ())?;
+                self.output.push('{');
+                let fields = instance.fields.borrow();
+                for (index, (name, value)) in fields.entries().iter().enumerate() {
+                    self.separator(index)?;
+                    self.entry(name, value)?;
+                }
+                self.close(fields.entries().is_empty(), '}');
+            }
+            _ => return Err(format!("Can't convert {} to JSON.", value)),
+        }
+        Ok(())
+    }
+
+    /// a key of an object and its value
+    fn entry(&mut self, key: &Value, value: &Value) -> Result<(), String> {
+        match key {
+            Value::Text(key) => self.string(key),
+            _ => return Err(format!("Can't convert map key {} to JSON, keys must be strings.", key)),
+        }
+        self.output.push_str(if self.indent > 0 { ": " } else { ":" });
+        self.write(value)
+    }
+
+    fn enter(&mut self, container: *const ()) -> Result<(), String> {
+        if self.open.contains(&container) {
+            return Err(String::from("Can't convert a list, map or instance that contains itself to JSON."));
+        }
+        self.open.push(container);
+        Ok(())
+    }
+
+    /// the comma before every element but the first, and the line break and indentation before every element
+    fn separator(&mut self, index: usize) -> Result<(), String> {
+        if index > 0 {
+            self.output.push(',');
+        }
+        self.newline(self.open.len());
+        Ok(())
+    }
+
+    /// ends a list or map, an empty one stays on one line
+    fn close(&mut self, empty: bool, bracket: char) {
+        self.open.pop();
+        if !empty {
+            self.newline(self.open.len());
+        }
+        self.output.push(bracket);
+    }
+
+    fn newline(&mut self, level: usize) {
+        if self.indent > 0 {
+            self.output.push('\n');
+            self.output.push_str(&" ".repeat(level * self.indent));
+        }
+    }
+
+    fn string(&mut self, text: &str) {
+        self.output.push('"');
+        for character in text.chars() {
+            match character {
+                '"' => self.output.push_str("\\\""),
+                '\\' => self.output.push_str("\\\\"),
+                '\n' => self.output.push_str("\\n"),
+                '\r' => self.output.push_str("\\r"),
+                '\t' => self.output.push_str("\\t"),
+                character if (character as u32) < 0x20 => {
+                    self.output.push_str(&format!("\\u{:04x}", character as u32))
+                }
+                character => self.output.push(character),
+            }
+        }
+        self.output.push('"');
+    }
+}
@@ -0,0 +1,63 @@
+use crate::interner::intern;
+use crate::interpreter::Interpreter;
+use crate::json::natives;
+use crate::tokens::Value;
+
+/// calls the function of the json module directly, lox strings can't contain the quotes that json needs
+fn call(name: &str, arguments: &[Value]) -> Result<Value, String> {
+    let function = natives().into_iter().find(|(function, _)| *function == name).unwrap().1;
+    return match function {
+        Value::Native(function) => (function.function)(&mut Interpreter::new(), arguments),
+        _ => panic!("not a native function"),
+    };
+}
+
+fn parse(text: &str) -> Result<Value, String> {
+    call("parse", &[Value::Text(intern(text))])
+}
+
+fn round_trip(text: &str, indent: f64) -> String {
+    let value = parse(text).unwrap();
+    format!("{:?}", call("stringify", &[value, Value::Numeric(indent)]).unwrap())
+}
+
+#[test]
+fn test_parse_values() {
+    assert_eq!(parse(" null ").unwrap(), Value::None);
+    assert_eq!(parse("true").unwrap(), Value::Boolean(true));
+    assert_eq!(parse("-12.5e1").unwrap(), Value::Numeric(-125.0));
+    assert_eq!(parse("\"a\\\"\\\\\\/\\n\\u00e9\\ud83c\\udf0d\"").unwrap(), Value::Text(intern("a\"\\/\né🌍")));
+    assert_eq!(format!("{:?}", parse("{\"a\": [1, {}], \"b\": \"日本\", \"a\": 2}").unwrap()), "{a: 2, b: 日本}");
+}
+
+#[test]
+fn test_parse_errors_report_the_byte_position() {
+    assert_eq!(parse("[1, 2").unwrap_err(), "Unexpected end of JSON at byte 5.");
+    assert_eq!(parse("[1,]").unwrap_err(), "Unexpected ']' in JSON at byte 3.");
+    assert_eq!(parse("{\"a\" 1}").unwrap_err(), "Unexpected '1' in JSON at byte 5.");
+    assert_eq!(parse("01").unwrap_err(), "Unexpected '1' in JSON at byte 1.");
+    assert_eq!(parse("\"é\" x").unwrap_err(), "Unexpected 'x' in JSON at byte 5.");
+    assert_eq!(parse("\"\\ud800\\u0041\"").unwrap_err(), "Invalid unicode escape at byte 1.");
+    assert_eq!(parse(&"[".repeat(1000)).unwrap_err(), "JSON is nested too deeply at byte 512.");
+}
+
+#[test]
+fn test_stringify() {
+    assert_eq!(round_trip("{\"a\": [1, 2.5, true, null], \"b\": {}, \"c\": []}", 0.0),
+               "{\"a\":[1,2.5,true,null],\"b\":{},\"c\":[]}");
+    assert_eq!(round_trip("{\"a\": [1, \"x\"], \"b\": {}}", 2.0), "{\n  \"a\": [\n    1,\n    \"x\"\n  ],\n  \"b\": {}\n}");
+    assert_eq!(round_trip("\"quote \\\" tab \\t \\u0001 é\"", 0.0), "\"quote \\\" tab \\t \\u0001 é\"");
+    assert_eq!(format!("{:?}", call("stringify", &[Value::Numeric(3.0)]).unwrap()), "3");
+    assert_eq!(round_trip("[1]", 1e15), format!("[\n{}1\n]", " ".repeat(10)));
+}
+
+#[test]
+fn test_stringify_errors() {
+    let list = Value::list(vec![Value::Numeric(1.0)]);
+    if let Value::List(elements) = &list {
+        elements.borrow_mut().push(list.clone());
+    }
+    assert_eq!(call("stringify", &[list]).unwrap_err(), "Can't convert a list, map or instance that contains itself to JSON.");
+    assert_eq!(call("stringify", &[Value::Numeric(f64::NAN)]).unwrap_err(), "Can't convert NaN to JSON.");
+    assert_eq!(call("stringify", &[Value::None, Value::Numeric(-1.0)]).unwrap_err(), "Indent must be a non-negative integer.");
+}
@@ -7,21 +7,33 @@ lazy_static! {
 pub static ref KEYWORDS: HashMap<&'static str, TokenType> = {
         let mut keywords = HashMap::new();
         keywords.insert("and", AND);
+        keywords.insert("as", AS);
+        keywords.insert("break", BREAK);
+        keywords.insert("catch", CATCH);
         keywords.insert("class", CLASS);
+        keywords.insert("continue", CONTINUE);
         keywords.insert("else", ELSE);
         keywords.insert("false", FALSE);
+        keywords.insert("finally", FINALLY);
         keywords.insert("for", FOR);
+        keywords.insert("from", FROM);
         keywords.insert("fun", FUN);
         keywords.insert("if", IF);
+        keywords.insert("import", IMPORT);
+        keywords.insert("in", IN);
         keywords.insert("nil", NIL);
         keywords.insert("or", OR);
         keywords.insert("print", PRINT);
         keywords.insert("return", RETURN);
         keywords.insert("super", SUPER);
         keywords.insert("this", THIS);
+        keywords.insert("throw", THROW);
+        keywords.insert("trait", TRAIT);
         keywords.insert("true", TRUE);
+        keywords.insert("try", TRY);
         keywords.insert("var", VAR);
         keywords.insert("while", WHILE);
+        keywords.insert("with", WITH);
         keywords
     };
 }
\ No newline at end of file
@@ -0,0 +1,117 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::gc::{self, Trace, Visit};
+use crate::iterator;
+use crate::native::NativeFunction;
+use crate::strings;
+use crate::tokens::Value;
+
+impl Trace for RefCell<Vec<Value>> {
+    fn trace(&self, visit: &mut Visit) -> bool {
+        return match self.try_borrow() {
+            Ok(elements) => {
+                for element in elements.iter() {
+                    gc::trace_value(element, visit);
+                }
+                true
+            }
+            Err(_) => false,
+        };
+    }
+
+    fn clear(&self) {
+        if let Ok(mut elements) = self.try_borrow_mut() {
+            elements.clear();
+        }
+    }
+}
+
+/// returns the method with the given name bound to the list,
+/// or None if lists don't have a method with that name
+pub fn method(list: &Rc<RefCell<Vec<Value>>>, name: &str) -> Option<Value> {
+    let list = Rc::clone(list);
+    let method = match name {
+        "push" => NativeFunction::new("push", 1, move |_, arguments| {
+            list.borrow_mut().push(arguments[0].clone());
+            Ok(Value::None)
+        }),
+        "pop" => NativeFunction::new("pop", 0, move |_, _| {
+            list.borrow_mut().pop().ok_or_else(|| String::from("Can't pop from an empty list."))
+        }),
+        "len" => NativeFunction::new("len", 0, move |_, _| {
+            Ok(Value::Numeric(list.borrow().len() as f64))
+        }),
+        "insert" => NativeFunction::new("insert", 2, move |_, arguments| {
+            let len = list.borrow().len();
+            // inserting right after the last element is allowed
+            let index = index(&arguments[0], len + 1)?;
+            list.borrow_mut().insert(index, arguments[1].clone());
+            Ok(Value::None)
+        }),
+        "remove" => NativeFunction::new("remove", 1, move |_, arguments| {
+            let len = list.borrow().len();
+            let index = index(&arguments[0], len)?;
+            Ok(list.borrow_mut().remove(index))
+        }),
+        "iterator" => NativeFunction::new("iterator", 0, move |_, _| {
+            Ok(iterator::of_list(&list))
+        }),
+        "join" => NativeFunction::new("join", 1, move |_, arguments| {
+            strings::join(&list.borrow(), &arguments[0])
+        }),
+        _ => return Option::None,
+    };
+    Some(Value::Native(Rc::new(method)))
+}
+
+/// returns the element at the given index
+pub fn get(list: &[Value], index_value: &Value) -> Result<Value, String> {
+    let index = index(index_value, list.len())?;
+    Ok(list[index].clone())
+}
+
+/// replaces the element at the given index
+pub fn set(list: &mut [Value], index_value: &Value, value: Value) -> Result<(), String> {
+    let index = index(index_value, list.len())?;
+    list[index] = value;
+    Ok(())
+}
+
+/// returns a new list with the elements from start (inclusive) to end (exclusive)
+/// a missing start means the start of the list, a missing end means the end of the list
+pub fn slice(list: &[Value], start: Option<&Value>, end: Option<&Value>) -> Result<Value, String> {
+    let (start, end) = bounds(list.len(), start, end)?;
+    Ok(Value::list(list[start..end].to_vec()))
+}
+
+/// converts the bounds of a slice of something with len elements to indexes
+pub fn bounds(len: usize, start: Option<&Value>, end: Option<&Value>) -> Result<(usize, usize), String> {
+    // both bounds may point just past the last element
+    let start = match start {
+        Some(value) => index(value, len + 1)?,
+        Option::None => 0,
+    };
+    let end = match end {
+        Some(value) => index(value, len + 1)?,
+        Option::None => len,
+    };
+    if start > end {
+        return Err(format!("Slice start {} is after slice end {}.", start, end));
+    }
+    Ok((start, end))
+}
+
+/// converts the value to an index that is smaller than len
+pub fn index(value: &Value, len: usize) -> Result<usize, String> {
+    return match value {
+        Value::Numeric(number) if number.fract() == 0.0 => {
+            if *number >= 0.0 && (*number as usize) < len {
+                Ok(*number as usize)
+            } else {
+                Err(format!("Index {} out of range.", number))
+            }
+        }
+        _ => Err(String::from("Index must be an integer.")),
+    };
+}
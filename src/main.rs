@@ -1,41 +1,99 @@
+// the explicit returns and the book's token names are kept on purpose
+#![allow(clippy::needless_return, clippy::upper_case_acronyms)]
+
 #[macro_use]
 extern crate lazy_static;
 
 use std::env;
 use std::fs::File;
-use std::io::{self, BufRead, Read, Write};
+use std::io::{self, Read, Write};
+use std::path::Path;
 use std::process;
+use std::thread;
 
-use crate::expression::{Visitor, AstPrinter};
+use crate::interpreter::Interpreter;
 
 mod scanner;
 mod tokens;
 mod keywords;
 mod expression;
 mod parser;
+mod interner;
+mod interpreter;
+mod native;
+mod list;
+mod map;
+mod statement;
+mod environment;
+mod gc;
+mod function;
+mod class;
+mod module;
+mod math;
+mod iterator;
+mod strings;
+mod system;
+mod clock;
+mod random;
+mod json;
+mod types;
+mod resolver;
 
 #[cfg(test)]
 mod scanner_tests;
+#[cfg(test)]
 mod parser_tests;
+#[cfg(test)]
+mod interpreter_tests;
+#[cfg(test)]
+mod resolver_tests;
+#[cfg(test)]
+mod json_tests;
+#[cfg(test)]
+mod gc_tests;
+
+/// the interpreter recurses for every lox call, which needs a lot more stack than the main thread has
+const STACK_SIZE: usize = 128 * 1024 * 1024;
 
 /// main
 /// no arguments: run interactively
 /// 1 argument: run the script file specified
+/// the options come first:
+/// --sandbox denies the native functions access to files, and imports of files outside the directory of the script
+/// --gc-stress runs the garbage collector on every allocation, to test it
 fn main() {
-    let args: Vec<String> = env::args().collect();
-
-    match args.len() {
-        1 => run_prompt(),
-        2 => run_file(args.get(0).unwrap()),
-        _ => {
-            println!("Usage: lox: [script]");
-            process::exit(64);
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut sandbox = false;
+    let mut gc_stress = false;
+    while args.first().is_some_and(|arg| arg.starts_with("--")) {
+        match args.remove(0).as_str() {
+            "--sandbox" => sandbox = true,
+            "--gc-stress" => gc_stress = true,
+            _ => usage(),
         }
     }
+
+    let interpreter = thread::Builder::new().stack_size(STACK_SIZE).spawn(move || {
+        // the heap belongs to the thread that runs the interpreter
+        if gc_stress {
+            gc::stress();
+        }
+        match args.len() {
+            0 => run_prompt(sandbox),
+            1 => run_file(&args[0], sandbox),
+            _ => usage(),
+        }
+    });
+    interpreter.unwrap().join().unwrap();
+}
+
+fn usage() -> ! {
+    println!("Usage: lox: [--sandbox] [--gc-stress] [script]");
+    process::exit(64);
 }
 
 /// run a script given in a file having the path specified
-fn run_file(path: &String) {
+fn run_file(path: &str, sandbox: bool) {
     // open file
     match File::open(path) {
         Ok(mut file) => {
@@ -43,11 +101,14 @@ fn run_file(path: &String) {
             let mut content = String::new();
             file.read_to_string(&mut content).unwrap();
 
-            // run the script
-            match run(content) {
-                // exit on runtime error
-                Err(_) => { process::exit(65); }
-                _ => {}
+            let mut interpreter = Interpreter::for_script(Path::new(path));
+            if sandbox {
+                interpreter.deny_file_access();
+            }
+
+            // run the script, exit on runtime error
+            if run(content, &mut interpreter).is_err() {
+                process::exit(65);
             }
         }
 
@@ -59,10 +120,15 @@ fn run_file(path: &String) {
 }
 
 /// run commands interactively
-fn run_prompt() {
-    // setup stdin to be able to read from it
+fn run_prompt(sandbox: bool) {
+    // stdin is not locked for the whole session, because readLine() reads from it too
     let stdin = io::stdin();
-    let mut handle = stdin.lock();
+
+    // variables defined on one line can be used on the next
+    let mut interpreter = Interpreter::new();
+    if sandbox {
+        interpreter.deny_file_access();
+    }
 
     // run continuously
     loop {
@@ -72,29 +138,28 @@ fn run_prompt() {
 
         // read string from stdin
         let mut content = String::new();
-        handle.read_line(&mut content).unwrap();
+        if stdin.read_line(&mut content).unwrap() == 0 {
+            // end of input
+            break;
+        }
         let source = String::from(content.trim());
 
-        // run input
-        match run(source) {
-            Err(message) => {
-                eprintln!("{}", message)
-            }
-            _ => {}
-        }
+        // run input, errors have been reported by the stage that found them, the session goes on
+        let _ = run(source, &mut interpreter);
     }
 }
 
 /// start interpreting and running the script
-fn run(source: String) -> Result<&'static str, &'static str> {
-    return match scanner::scan_tokens(source.as_str()) {
-        Ok(tokens) => {
-            let expr = parser::parse(tokens);
-            println!("{:?}", AstPrinter {}.visit_expr(&expr));
-            Ok("Ok")
-        }
-        Err(code) => {
-            Err(code)
+fn run(source: String, interpreter: &mut Interpreter) -> Result<&'static str, &'static str> {
+    let tokens = scanner::scan_tokens(source.as_str())?;
+    let statements = parser::parse(tokens)?;
+    resolver::resolve(&statements)?;
+
+    return match interpreter.interpret(&statements) {
+        Ok(_) => Ok("Ok"),
+        Err(error) => {
+            eprintln!("{}", error);
+            Err("Runtime error")
         }
     };
-}
\ No newline at end of file
+}
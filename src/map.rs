@@ -0,0 +1,141 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::gc::{self, Trace, Visit};
+use crate::interner::Symbol;
+use crate::iterator;
+use crate::native::NativeFunction;
+use crate::tokens::Value;
+
+/// the values that can be used as map keys
+/// numbers are stored as their bits, with -0 normalized to 0, so they can be hashed
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Text(Symbol),
+    Numeric(u64),
+    Boolean(bool),
+}
+
+impl Key {
+    fn from(value: &Value) -> Result<Key, String> {
+        return match value {
+            Value::Text(text) => Ok(Key::Text(text.clone())),
+            Value::Numeric(number) if number.is_nan() => Err(String::from("Map key can't be NaN.")),
+            Value::Numeric(number) => Ok(Key::Numeric((number + 0.0).to_bits())),
+            Value::Boolean(boolean) => Ok(Key::Boolean(*boolean)),
+            _ => Err(String::from("Map keys must be strings, numbers or booleans.")),
+        };
+    }
+}
+
+/// map that iterates its entries in the order in which the keys were first inserted
+#[derive(Default)]
+pub struct Map {
+    // key value pairs, the key is kept as a value to hand it back to lox code
+    entries: Vec<(Value, Value)>,
+    // position of every key in entries
+    positions: HashMap<Key, usize>,
+}
+
+impl Map {
+    /// key value pairs in insertion order
+    pub fn entries(&self) -> &[(Value, Value)] {
+        &self.entries
+    }
+
+    pub fn get(&self, key: &Value) -> Result<Option<Value>, String> {
+        let key = Key::from(key)?;
+        Ok(self.positions.get(&key).map(|position| self.entries[*position].1.clone()))
+    }
+
+    /// overwriting the value of an existing key keeps its position
+    pub fn insert(&mut self, key: Value, value: Value) -> Result<(), String> {
+        let hashed = Key::from(&key)?;
+        match self.positions.get(&hashed) {
+            Some(position) => {
+                self.entries[*position].1 = value;
+            }
+            None => {
+                self.positions.insert(hashed, self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+        Ok(())
+    }
+
+    /// removes the key and returns its value, if it was present
+    pub fn remove(&mut self, key: &Value) -> Result<Option<Value>, String> {
+        let key = Key::from(key)?;
+        return match self.positions.remove(&key) {
+            Some(removed) => {
+                // entries after the removed one move up one place
+                for position in self.positions.values_mut() {
+                    if *position > removed {
+                        *position -= 1;
+                    }
+                }
+                Ok(Some(self.entries.remove(removed).1))
+            }
+            None => Ok(None),
+        };
+    }
+}
+
+impl Trace for RefCell<Map> {
+    fn trace(&self, visit: &mut Visit) -> bool {
+        return match self.try_borrow() {
+            Ok(map) => {
+                trace(&map, visit);
+                true
+            }
+            Err(_) => false,
+        };
+    }
+
+    fn clear(&self) {
+        if let Ok(mut map) = self.try_borrow_mut() {
+            *map = Map::default();
+        }
+    }
+}
+
+/// visits the values of the map, the keys are strings, numbers or booleans, which are not managed
+pub fn trace(map: &Map, visit: &mut Visit) {
+    for (_, value) in map.entries() {
+        gc::trace_value(value, visit);
+    }
+}
+
+/// returns the method with the given name bound to the map,
+/// or None if maps don't have a method with that name
+pub fn method(map: &Rc<RefCell<Map>>, name: &str) -> Option<Value> {
+    let map = Rc::clone(map);
+    let method = match name {
+        "keys" => NativeFunction::new("keys", 0, move |_, _| {
+            Ok(Value::list(map.borrow().entries().iter().map(|(key, _)| key.clone()).collect()))
+        }),
+        "values" => NativeFunction::new("values", 0, move |_, _| {
+            Ok(Value::list(map.borrow().entries().iter().map(|(_, value)| value.clone()).collect()))
+        }),
+        "has" => NativeFunction::new("has", 1, move |_, arguments| {
+            Ok(Value::Boolean(map.borrow().get(&arguments[0])?.is_some()))
+        }),
+        "remove" => NativeFunction::new("remove", 1, move |_, arguments| {
+            Ok(map.borrow_mut().remove(&arguments[0])?.unwrap_or(Value::None))
+        }),
+        "len" => NativeFunction::new("len", 0, move |_, _| {
+            Ok(Value::Numeric(map.borrow().entries().len() as f64))
+        }),
+        "iterator" => NativeFunction::new("iterator", 0, move |_, _| {
+            Ok(iterator::of_map(&map.borrow()))
+        }),
+        _ => return None,
+    };
+    Some(Value::Native(Rc::new(method)))
+}
+
+/// returns the value for the key, it is an error if the key is missing
+pub fn get(map: &Map, key: &Value) -> Result<Value, String> {
+    map.get(key)?.ok_or_else(|| format!("Undefined key '{}'.", key))
+}
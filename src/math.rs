@@ -0,0 +1,70 @@
+use std::f64::consts;
+
+use crate::native::NativeFunction;
+use crate::tokens::Value;
+
+/// the functions and constants of the math module, which are also globals
+pub fn natives() -> Vec<(&'static str, Value)> {
+    let functions = vec![
+        unary("sqrt", f64::sqrt),
+        binary("pow", f64::powf),
+        unary("floor", f64::floor),
+        unary("ceil", f64::ceil),
+        // halfway cases round away from zero
+        unary("round", f64::round),
+        unary("abs", f64::abs),
+        extreme("min", f64::min),
+        extreme("max", f64::max),
+        unary("sin", f64::sin),
+        unary("cos", f64::cos),
+        unary("tan", f64::tan),
+        unary("asin", f64::asin),
+        unary("acos", f64::acos),
+        unary("atan", f64::atan),
+        binary("atan2", f64::atan2),
+        // the natural logarithm
+        unary("log", f64::ln),
+        unary("exp", f64::exp),
+        NativeFunction::new("isNaN", 1, |_, arguments| {
+            Ok(Value::Boolean(number("isNaN", &arguments[0])?.is_nan()))
+        }),
+    ];
+
+    let mut natives: Vec<(&'static str, Value)> = functions.into_iter()
+        .map(|function| (function.name, Value::native(function)))
+        .collect();
+    natives.push(("PI", Value::Numeric(consts::PI)));
+    natives.push(("E", Value::Numeric(consts::E)));
+    natives.push(("INF", Value::Numeric(f64::INFINITY)));
+    natives
+}
+
+fn unary(name: &'static str, function: fn(f64) -> f64) -> NativeFunction {
+    NativeFunction::new(name, 1, move |_, arguments| {
+        Ok(Value::Numeric(function(number(name, &arguments[0])?)))
+    })
+}
+
+fn binary(name: &'static str, function: fn(f64, f64) -> f64) -> NativeFunction {
+    NativeFunction::new(name, 2, move |_, arguments| {
+        Ok(Value::Numeric(function(number(name, &arguments[0])?, number(name, &arguments[1])?)))
+    })
+}
+
+/// min or max of one or more numbers
+fn extreme(name: &'static str, function: fn(f64, f64) -> f64) -> NativeFunction {
+    NativeFunction::variadic(name, 1, move |_, arguments| {
+        let mut result = number(name, &arguments[0])?;
+        for argument in &arguments[1..] {
+            result = function(result, number(name, argument)?);
+        }
+        Ok(Value::Numeric(result))
+    })
+}
+
+fn number(name: &str, argument: &Value) -> Result<f64, String> {
+    return match argument {
+        Value::Numeric(number) => Ok(*number),
+        _ => Err(format!("Arguments of {}() must be numbers.", name)),
+    };
+}
@@ -0,0 +1,46 @@
+use std::cell::RefCell;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use crate::environment::Environment;
+
+/// an imported file
+/// its global variables live in an environment of their own, other files see them as properties of the module
+pub struct Module {
+    pub path: PathBuf,
+    pub globals: Rc<RefCell<Environment>>,
+}
+
+impl Module {
+    pub fn name(&self) -> String {
+        file_name(&self.path)
+    }
+}
+
+/// the last part of the path, which is enough to tell the files in an error message apart
+pub fn file_name(path: &Path) -> String {
+    return match path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.display().to_string(),
+    };
+}
+
+/// the path with the . and .. parts taken out, without looking at the file system, so links are not followed
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // the parent of the root is the root
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,27 @@
+use crate::interpreter::Interpreter;
+use crate::tokens::Value;
+
+type NativeFn = dyn Fn(&mut Interpreter, &[Value]) -> Result<Value, String>;
+
+/// a function implemented in rust that can be called from lox code
+/// it returns the error message for a runtime error, the interpreter adds the location of the call
+pub struct NativeFunction {
+    pub name: &'static str,
+    // the minimum number of arguments if the function is variadic
+    pub arity: usize,
+    pub variadic: bool,
+    pub function: Box<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new<F>(name: &'static str, arity: usize, function: F) -> NativeFunction
+        where F: Fn(&mut Interpreter, &[Value]) -> Result<Value, String> + 'static {
+        NativeFunction { name, arity, variadic: false, function: Box::new(function) }
+    }
+
+    /// a function that takes any number of arguments from the given arity up
+    pub fn variadic<F>(name: &'static str, arity: usize, function: F) -> NativeFunction
+        where F: Fn(&mut Interpreter, &[Value]) -> Result<Value, String> + 'static {
+        NativeFunction { name, arity, variadic: true, function: Box::new(function) }
+    }
+}
@@ -1,13 +1,27 @@
-use crate::expression::Expr;
+use std::rc::Rc;
+
+use crate::expression::{Depth, Expr};
 use crate::expression::Expr::*;
+use crate::interner::intern;
+use crate::statement::{Class, Function, Stmt, Trait};
 use crate::tokens::{Token, TokenType};
 use crate::tokens::TokenType::*;
 use crate::tokens::Value::*;
 
-pub fn parse(tokens: Vec<Token>) -> Expr {
-    Parser::new(tokens).parse()
+/// public function for parsing a list of tokens into statements
+/// parse errors are reported as soon as they are found,
+/// after an error the parser skips to the next statement to find more errors
+pub fn parse(tokens: Vec<Token>) -> Result<Vec<Stmt>, &'static str> {
+    return Parser::new(tokens).parse();
 }
 
+/// signals that the parser ran into an error, which has already been reported
+#[derive(Debug)]
+struct ParseError;
+
+type ParseResult = Result<Expr, ParseError>;
+type StmtResult = Result<Stmt, ParseError>;
+
 struct Parser {
     tokens: Vec<Token>,
     current: usize,
@@ -18,36 +32,512 @@ impl Parser {
         Parser { tokens, current: 0 }
     }
 
-    fn parse(&mut self) -> Expr {
-        self.expression()
+    fn parse(&mut self) -> Result<Vec<Stmt>, &'static str> {
+        let mut statements = Vec::new();
+        let mut error_occurred = false;
+
+        while !self.is_at_end() {
+            match self.declaration() {
+                Ok(statement) => statements.push(statement),
+                Err(_) => {
+                    error_occurred = true;
+                    self.synchronize();
+                }
+            }
+        }
+
+        return if error_occurred { Err("Error occurred") } else { Ok(statements) };
+    }
+
+    fn declaration(&mut self) -> StmtResult {
+        if self.match_token(&[CLASS]) {
+            return self.class_declaration();
+        }
+        // a name after fun makes it a declaration, otherwise it is a lambda at the start of an expression statement
+        if self.check(FUN) && self.peek_next().token_type == IDENTIFIER {
+            self.advance();
+            return Ok(Stmt::Function(self.function("function")?));
+        }
+        if self.match_token(&[TRAIT]) {
+            return self.trait_declaration();
+        }
+        if self.match_token(&[IMPORT]) {
+            return self.import_declaration();
+        }
+        if self.match_token(&[FROM]) {
+            return self.selective_import_declaration();
+        }
+        if self.match_token(&[VAR]) {
+            return self.var_declaration();
+        }
+
+        self.statement()
+    }
+
+    /// class name ( < superclass )? ( with trait ( , trait )* )? { member* }
+    /// where a member is a method, a getter, or a method or field of the class itself:
+    /// name ( params ) { body }, name { body }, class name ( params ) { body }, class var name ( = value )? ;
+    fn class_declaration(&mut self) -> StmtResult {
+        let name = self.consume_token(IDENTIFIER, "Expect class name.")?;
+
+        let superclass = if self.match_token(&[LESS]) {
+            Some(Variable(self.consume_token(IDENTIFIER, "Expect superclass name.")?, Depth::default()))
+        } else {
+            Option::None
+        };
+
+        let mut traits = Vec::new();
+        if self.match_token(&[WITH]) {
+            loop {
+                traits.push(Variable(self.consume_token(IDENTIFIER, "Expect trait name.")?, Depth::default()));
+                if !self.match_token(&[COMMA]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume_token(LEFTBRACE, "Expect '{' before class body.")?;
+        let mut methods = Vec::new();
+        let mut class_methods = Vec::new();
+        let mut class_fields = Vec::new();
+        while !self.check(RIGHTBRACE) && !self.is_at_end() {
+            if !self.match_token(&[CLASS]) {
+                methods.push(self.method()?);
+            } else if self.match_token(&[VAR]) {
+                let name = self.consume_token(IDENTIFIER, "Expect field name.")?;
+                let initializer = if self.match_token(&[EQUAL]) { Some(self.expression()?) } else { Option::None };
+                self.consume_token(SEMICOLON, "Expect ';' after field declaration.")?;
+                class_fields.push((name, initializer));
+            } else {
+                class_methods.push(self.method()?);
+            }
+        }
+        self.consume_token(RIGHTBRACE, "Expect '}' after class body.")?;
+
+        Ok(Stmt::Class(Class { name, superclass, traits, methods, class_methods, class_fields }))
+    }
+
+    /// trait name { method* }, where the methods can be getters
+    fn trait_declaration(&mut self) -> StmtResult {
+        let name = self.consume_token(IDENTIFIER, "Expect trait name.")?;
+        self.consume_token(LEFTBRACE, "Expect '{' before trait body.")?;
+
+        let mut methods = Vec::new();
+        while !self.check(RIGHTBRACE) && !self.is_at_end() {
+            methods.push(self.method()?);
+        }
+        self.consume_token(RIGHTBRACE, "Expect '}' after trait body.")?;
+
+        Ok(Stmt::Trait(Trait { name, methods }))
+    }
+
+    /// a method, or a getter when the name is followed by the body instead of the parameters
+    fn method(&mut self) -> Result<Rc<Function>, ParseError> {
+        if self.check(IDENTIFIER) && self.peek_next().token_type == LEFTBRACE {
+            let name = self.advance();
+            self.advance();
+            let body = self.block()?;
+            return Ok(Rc::new(Function { name, params: Vec::new(), body, getter: true }));
+        }
+        self.function("method")
+    }
+
+    /// the kind is used in the error messages
+    fn function(&mut self, kind: &str) -> Result<Rc<Function>, ParseError> {
+        let name = self.consume_token(IDENTIFIER, &format!("Expect {} name.", kind))?;
+        self.consume_token(LEFTPAREN, &format!("Expect '(' after {} name.", kind))?;
+        let params = self.parameters()?;
+
+        self.consume_token(LEFTBRACE, &format!("Expect '{{' before {} body.", kind))?;
+        let body = self.block()?;
+        Ok(Rc::new(Function { name, params, body, getter: false }))
+    }
+
+    /// the parameters after the opening parenthesis, and the closing parenthesis
+    fn parameters(&mut self) -> Result<Vec<Token>, ParseError> {
+        let mut params = Vec::new();
+        if !self.check(RIGHTPAREN) {
+            loop {
+                if params.len() >= 255 {
+                    // reported, but the parser is not confused, so it doesn't need to synchronize
+                    self.error(&self.peek(), "Can't have more than 255 parameters.");
+                }
+                params.push(self.consume_token(IDENTIFIER, "Expect parameter name.")?);
+                if !self.match_token(&[COMMA]) {
+                    break;
+                }
+            }
+        }
+        self.consume_token(RIGHTPAREN, "Expect ')' after parameters.")?;
+        Ok(params)
+    }
+
+    /// import "path" as name;
+    fn import_declaration(&mut self) -> StmtResult {
+        let path = self.consume_token(STRING, "Expect module path after 'import'.")?;
+        self.consume_token(AS, "Expect 'as' after module path.")?;
+        let name = self.consume_token(IDENTIFIER, "Expect module name after 'as'.")?;
+        self.consume_token(SEMICOLON, "Expect ';' after import.")?;
+        Ok(Stmt::Import(path, name))
+    }
+
+    /// from "path" import name, name;
+    fn selective_import_declaration(&mut self) -> StmtResult {
+        let path = self.consume_token(STRING, "Expect module path after 'from'.")?;
+        self.consume_token(IMPORT, "Expect 'import' after module path.")?;
+
+        let mut names = Vec::new();
+        loop {
+            names.push(self.consume_token(IDENTIFIER, "Expect name to import.")?);
+            if !self.match_token(&[COMMA]) {
+                break;
+            }
+        }
+        self.consume_token(SEMICOLON, "Expect ';' after import.")?;
+        Ok(Stmt::FromImport(path, names))
+    }
+
+    fn var_declaration(&mut self) -> StmtResult {
+        let name = self.consume_token(IDENTIFIER, "Expect variable name.")?;
+
+        let initializer = if self.match_token(&[EQUAL]) { Some(self.expression()?) } else { Option::None };
+
+        self.consume_token(SEMICOLON, "Expect ';' after variable declaration.")?;
+        Ok(Stmt::Var(name, initializer))
+    }
+
+    fn statement(&mut self) -> StmtResult {
+        if self.match_token(&[BREAK, CONTINUE]) {
+            return self.jump_statement();
+        }
+        if self.match_token(&[FOR]) {
+            return self.for_statement();
+        }
+        if self.match_token(&[IF]) {
+            return self.if_statement();
+        }
+        if self.match_token(&[PRINT]) {
+            return self.print_statement();
+        }
+        if self.match_token(&[RETURN]) {
+            return self.return_statement();
+        }
+        if self.match_token(&[THROW]) {
+            return self.throw_statement();
+        }
+        if self.match_token(&[TRY]) {
+            return self.try_statement();
+        }
+        if self.match_token(&[WHILE]) {
+            return self.while_statement();
+        }
+        // at the start of a statement a brace opens a block, not a map literal
+        if self.match_token(&[LEFTBRACE]) {
+            return Ok(Stmt::Block(self.block()?));
+        }
+
+        self.expression_statement()
+    }
+
+    /// break or continue, the resolver checks that they are inside a loop
+    fn jump_statement(&mut self) -> StmtResult {
+        let keyword = self.previous();
+        self.consume_token(SEMICOLON, &format!("Expect ';' after '{}'.", keyword.lexeme))?;
+
+        return if keyword.token_type == BREAK { Ok(Stmt::Break(keyword)) } else { Ok(Stmt::Continue(keyword)) };
+    }
+
+    /// either for ( IDENTIFIER in expression ) statement
+    /// or for ( initializer? ; condition? ; increment? ) statement
+    fn for_statement(&mut self) -> StmtResult {
+        self.consume_token(LEFTPAREN, "Expect '(' after 'for'.")?;
+
+        if self.check(IDENTIFIER) && self.peek_next().token_type == IN {
+            return self.for_in_statement();
+        }
+
+        let initializer = if self.match_token(&[SEMICOLON]) {
+            Option::None
+        } else if self.match_token(&[VAR]) {
+            Some(self.var_declaration()?)
+        } else {
+            Some(self.expression_statement()?)
+        };
+
+        let condition = if self.check(SEMICOLON) { Literal(Boolean(true)) } else { self.expression()? };
+        self.consume_token(SEMICOLON, "Expect ';' after loop condition.")?;
+
+        let increment = if self.check(RIGHTPAREN) { Option::None } else { Some(self.expression()?) };
+        self.consume_token(RIGHTPAREN, "Expect ')' after for clauses.")?;
+
+        let body = self.statement()?;
+
+        // the for loop is turned into a while loop that keeps the increment apart,
+        // so that it also runs when the body continues
+        let while_loop = Stmt::While(condition, Box::new(body), increment);
+        return match initializer {
+            Some(initializer) => Ok(Stmt::Block(vec![initializer, while_loop])),
+            Option::None => Ok(while_loop),
+        };
+    }
+
+    fn for_in_statement(&mut self) -> StmtResult {
+        let name = self.advance();
+        self.consume_token(IN, "Expect 'in' after loop variable.")?;
+        let iterable = self.expression()?;
+        self.consume_token(RIGHTPAREN, "Expect ')' after for clauses.")?;
+
+        let body = self.statement()?;
+        Ok(Stmt::ForIn(name, iterable, Box::new(body)))
+    }
+
+    fn if_statement(&mut self) -> StmtResult {
+        self.consume_token(LEFTPAREN, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume_token(RIGHTPAREN, "Expect ')' after if condition.")?;
+
+        let then_branch = self.statement()?;
+        let else_branch = if self.match_token(&[ELSE]) { Some(Box::new(self.statement()?)) } else { Option::None };
+
+        Ok(Stmt::If(condition, Box::new(then_branch), else_branch))
+    }
+
+    fn while_statement(&mut self) -> StmtResult {
+        self.consume_token(LEFTPAREN, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume_token(RIGHTPAREN, "Expect ')' after condition.")?;
+
+        let body = self.statement()?;
+        Ok(Stmt::While(condition, Box::new(body), Option::None))
+    }
+
+    fn print_statement(&mut self) -> StmtResult {
+        let value = self.expression()?;
+        self.consume_token(SEMICOLON, "Expect ';' after value.")?;
+        Ok(Stmt::Print(value))
+    }
+
+    /// return, the resolver checks that it is inside a function
+    fn return_statement(&mut self) -> StmtResult {
+        let keyword = self.previous();
+        let value = if self.check(SEMICOLON) { Option::None } else { Some(self.expression()?) };
+        self.consume_token(SEMICOLON, "Expect ';' after return value.")?;
+        Ok(Stmt::Return(keyword, value))
+    }
+
+    fn throw_statement(&mut self) -> StmtResult {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume_token(SEMICOLON, "Expect ';' after thrown value.")?;
+        Ok(Stmt::Throw(keyword, value))
+    }
+
+    /// try block, followed by a catch clause, a finally clause or both
+    fn try_statement(&mut self) -> StmtResult {
+        self.consume_token(LEFTBRACE, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let catch = if self.match_token(&[CATCH]) {
+            self.consume_token(LEFTPAREN, "Expect '(' after 'catch'.")?;
+            let name = self.consume_token(IDENTIFIER, "Expect error variable name.")?;
+            self.consume_token(RIGHTPAREN, "Expect ')' after error variable.")?;
+            self.consume_token(LEFTBRACE, "Expect '{' before catch body.")?;
+            Some((name, self.block()?))
+        } else {
+            Option::None
+        };
+
+        let finally = if self.match_token(&[FINALLY]) {
+            self.consume_token(LEFTBRACE, "Expect '{' after 'finally'.")?;
+            Some(self.block()?)
+        } else {
+            Option::None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(self.error(&self.peek(), "Expect 'catch' or 'finally' after try block."));
+        }
+        Ok(Stmt::Try(body, catch, finally))
+    }
+
+    fn expression_statement(&mut self) -> StmtResult {
+        let expr = self.expression()?;
+        self.consume_token(SEMICOLON, "Expect ';' after expression.")?;
+        Ok(Stmt::Expression(expr))
+    }
+
+    /// the statements of a block, after the opening brace
+    fn block(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        let mut statements = Vec::new();
+
+        while !self.check(RIGHTBRACE) && !self.is_at_end() {
+            statements.push(self.declaration()?);
+        }
+
+        self.consume_token(RIGHTBRACE, "Expect '}' after block.")?;
+        Ok(statements)
+    }
+
+    fn expression(&mut self) -> ParseResult {
+        self.comma()
+    }
+
+    /// expressions separated by commas are evaluated left to right, the value is that of the last one
+    fn comma(&mut self) -> ParseResult {
+        let mut expr = self.assignment()?;
+
+        while self.match_token(&[COMMA]) {
+            let operator = self.previous();
+            let right = self.assignment()?;
+            expr = Binary(Box::new(expr), operator, Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn assignment(&mut self) -> ParseResult {
+        let expr = self.conditional()?;
+
+        if self.match_token(&[EQUAL]) {
+            let equals = self.previous();
+            let value = self.assignment()?;
+
+            return match expr {
+                Variable(name, _) => Ok(Assign(name, Box::new(value), Depth::default())),
+                Index(object, bracket, index) => Ok(IndexSet(object, bracket, index, Box::new(value))),
+                Get(object, name) => Ok(Set(object, name, Box::new(value))),
+                _ => Err(self.error(&equals, "Invalid assignment target.")),
+            };
+        }
+
+        if self.match_token(&[PLUSEQUAL, MINUSEQUAL, STAREQUAL, SLASHEQUAL]) {
+            let operator = self.previous();
+            let value = self.assignment()?;
+
+            self.check_assignable(&expr, &operator)?;
+            return Ok(CompoundAssign(Box::new(expr), operator, Box::new(value)));
+        }
+
+        Ok(expr)
+    }
+
+    /// only variables, indexes and properties can be updated by compound assignments and increments
+    fn check_assignable(&self, target: &Expr, operator: &Token) -> Result<(), ParseError> {
+        return match target {
+            Variable(_, _) | Index(_, _, _) | Get(_, _) => Ok(()),
+            _ => Err(self.error(operator, "Invalid assignment target.")),
+        };
+    }
+
+    /// condition ? then : else, which is right-associative
+    fn conditional(&mut self) -> ParseResult {
+        let condition = self.or()?;
+
+        if self.match_token(&[QUESTION]) {
+            // like in C anything can go between ? and :, even an assignment or a comma expression
+            let then_branch = self.expression()?;
+            self.consume_token(COLON, "Expect ':' after then branch of conditional expression.")?;
+            let else_branch = self.conditional()?;
+            return Ok(Conditional(Box::new(condition), Box::new(then_branch), Box::new(else_branch)));
+        }
+
+        Ok(condition)
     }
 
-    fn expression(&mut self) -> Expr {
-        self.equality()
+    fn or(&mut self) -> ParseResult {
+        let mut expr = self.and()?;
+
+        while self.match_token(&[OR]) {
+            let operator = self.previous();
+            let right = self.and()?;
+            expr = Logical(Box::new(expr), operator, Box::new(right));
+        }
+
+        Ok(expr)
     }
 
-    fn equality(&mut self) -> Expr {
-        let mut expr = self.comparison();
+    fn and(&mut self) -> ParseResult {
+        let mut expr = self.equality()?;
+
+        while self.match_token(&[AND]) {
+            let operator = self.previous();
+            let right = self.equality()?;
+            expr = Logical(Box::new(expr), operator, Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn equality(&mut self) -> ParseResult {
+        let mut expr = self.comparison()?;
 
         while self.match_token(&[BANGEQUAL, EQUALEQUAL]) {
             let operator = self.previous();
-            let right = self.comparison();
+            let right = self.comparison()?;
             expr = Binary(Box::new(expr), operator, Box::new(right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn comparison(&mut self) -> Expr {
-        let mut expr = self.addition();
+    fn comparison(&mut self) -> ParseResult {
+        let mut expr = self.bitwise_or()?;
 
         while self.match_token(&[GREATER, GREATEREQUAL, LESS, LESSEQUAL]) {
             let operator = self.previous();
-            let right = self.addition();
+            let right = self.bitwise_or()?;
+            expr = Binary(Box::new(expr), operator, Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn bitwise_or(&mut self) -> ParseResult {
+        let mut expr = self.bitwise_xor()?;
+
+        while self.match_token(&[PIPE]) {
+            let operator = self.previous();
+            let right = self.bitwise_xor()?;
             expr = Binary(Box::new(expr), operator, Box::new(right));
         }
 
-        expr
+        Ok(expr)
+    }
+
+    fn bitwise_xor(&mut self) -> ParseResult {
+        let mut expr = self.bitwise_and()?;
+
+        while self.match_token(&[CARET]) {
+            let operator = self.previous();
+            let right = self.bitwise_and()?;
+            expr = Binary(Box::new(expr), operator, Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn bitwise_and(&mut self) -> ParseResult {
+        let mut expr = self.shift()?;
+
+        while self.match_token(&[AMPERSAND]) {
+            let operator = self.previous();
+            let right = self.shift()?;
+            expr = Binary(Box::new(expr), operator, Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> ParseResult {
+        let mut expr = self.addition()?;
+
+        while self.match_token(&[LESSLESS, GREATERGREATER]) {
+            let operator = self.previous();
+            let right = self.addition()?;
+            expr = Binary(Box::new(expr), operator, Box::new(right));
+        }
+
+        Ok(expr)
     }
 
     fn match_token(&mut self, tokens: &[TokenType]) -> bool {
@@ -73,6 +563,11 @@ impl Parser {
         return self.tokens[self.current].clone();
     }
 
+    /// returns the token after the next one, or the last token (EOF)
+    fn peek_next(&self) -> Token {
+        return self.tokens[(self.current + 1).min(self.tokens.len() - 1)].clone();
+    }
+
     fn advance(&mut self) -> Token {
         if !self.is_at_end() {
             self.current += 1;
@@ -88,70 +583,328 @@ impl Parser {
         self.tokens[self.current - 1].clone()
     }
 
-    fn addition(&mut self) -> Expr {
-        let mut expr = self.multiplication();
+    fn addition(&mut self) -> ParseResult {
+        let mut expr = self.multiplication()?;
 
         while self.match_token(&[MINUS, PLUS]) {
             let operator = self.previous();
-            let right = self.multiplication();
+            let right = self.multiplication()?;
             expr = Binary(Box::new(expr), operator, Box::new(right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn multiplication(&mut self) -> Expr {
-        let mut expr = self.unary();
+    fn multiplication(&mut self) -> ParseResult {
+        let mut expr = self.unary()?;
 
-        while self.match_token(&[SLASH, STAR]) {
+        while self.match_token(&[SLASH, STAR, PERCENT, TILDESLASH]) {
             let operator = self.previous();
-            let right = self.unary();
+            let right = self.unary()?;
             expr = Binary(Box::new(expr), operator, Box::new(right));
         }
 
-        return expr;
+        return Ok(expr);
     }
 
-    fn unary(&mut self) -> Expr {
-        if self.match_token(&[BANG, MINUS]) {
+    fn unary(&mut self) -> ParseResult {
+        if self.match_token(&[PLUSPLUS, MINUSMINUS]) {
+            let operator = self.previous();
+            let target = self.unary()?;
+
+            self.check_assignable(&target, &operator)?;
+            return Ok(Increment(Box::new(target), operator, false));
+        }
+
+        if self.match_token(&[BANG, MINUS, TILDE]) {
             let operator = self.previous();
-            let right = self.unary();
-            return Unary(operator, Box::new(right));
+            let right = self.unary()?;
+            return Ok(Unary(operator, Box::new(right)));
+        }
+
+        return self.exponent();
+    }
+
+    /// ** binds tighter than a unary operator on its left, so -2 ** 2 is -(2 ** 2),
+    /// and it is right-associative, 2 ** 3 ** 2 is 2 ** (3 ** 2)
+    fn exponent(&mut self) -> ParseResult {
+        let expr = self.postfix()?;
+
+        if self.match_token(&[STARSTAR]) {
+            let operator = self.previous();
+            let right = self.unary()?;
+            return Ok(Binary(Box::new(expr), operator, Box::new(right)));
+        }
+
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> ParseResult {
+        let expr = self.call()?;
+
+        if self.match_token(&[PLUSPLUS, MINUSMINUS]) {
+            let operator = self.previous();
+
+            self.check_assignable(&expr, &operator)?;
+            return Ok(Increment(Box::new(expr), operator, true));
+        }
+
+        Ok(expr)
+    }
+
+    /// calls, property access and indexing, which can be chained
+    fn call(&mut self) -> ParseResult {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.match_token(&[LEFTPAREN]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_token(&[DOT]) {
+                let name = self.consume_token(IDENTIFIER, "Expect property name after '.'.")?;
+                expr = Get(Box::new(expr), name);
+            } else if self.match_token(&[LEFTBRACKET]) {
+                expr = self.finish_index(expr)?;
+            } else {
+                break;
+            }
         }
 
-        return self.primary();
+        Ok(expr)
     }
 
-    fn primary(&mut self) -> Expr {
+    fn finish_call(&mut self, callee: Expr) -> ParseResult {
+        let mut arguments = Vec::new();
+        if !self.check(RIGHTPAREN) {
+            loop {
+                arguments.push(self.assignment()?);
+                if !self.match_token(&[COMMA]) {
+                    break;
+                }
+            }
+        }
+
+        let paren = self.consume_token(RIGHTPAREN, "Expect ')' after arguments.")?;
+        Ok(Call(Box::new(callee), paren, arguments))
+    }
+
+    /// parses either an index xs[i] or a slice xs[start:end] where both bounds are optional
+    fn finish_index(&mut self, object: Expr) -> ParseResult {
+        let bracket = self.previous();
+
+        let start = if self.check(COLON) { Option::None } else { Some(Box::new(self.expression()?)) };
+
+        if self.match_token(&[COLON]) {
+            let end = if self.check(RIGHTBRACKET) { Option::None } else { Some(Box::new(self.expression()?)) };
+            self.consume_token(RIGHTBRACKET, "Expect ']' after slice.")?;
+            return Ok(Slice(Box::new(object), bracket, start, end));
+        }
+
+        self.consume_token(RIGHTBRACKET, "Expect ']' after index.")?;
+        // start is always present here, because a missing start requires a colon
+        Ok(Index(Box::new(object), bracket, start.unwrap()))
+    }
+
+    fn primary(&mut self) -> ParseResult {
         if self.match_token(&[FALSE]) {
-            return Literal(Boolean(false));
+            return Ok(Literal(Boolean(false)));
         }
         if self.match_token(&[TRUE]) {
-            return Literal(Boolean(true));
+            return Ok(Literal(Boolean(true)));
         }
 
         if self.match_token(&[NIL]) {
-            return Literal(None);
+            return Ok(Literal(None));
         }
         if self.match_token(&[NUMBER, STRING]) {
-            return Literal(self.previous().literal);
+            return Ok(Literal(self.previous().literal));
+        }
+
+        if self.match_token(&[INTERPOLATION]) {
+            return self.interpolation();
+        }
+
+        if self.match_token(&[THIS]) {
+            return Ok(This(self.previous(), Depth::default()));
+        }
+        if self.match_token(&[SUPER]) {
+            let keyword = self.previous();
+            self.consume_token(DOT, "Expect '.' after 'super'.")?;
+            let method = self.consume_token(IDENTIFIER, "Expect superclass method name.")?;
+            return Ok(Super(keyword, method, Depth::default()));
+        }
+
+        if self.match_token(&[IDENTIFIER]) {
+            return Ok(Variable(self.previous(), Depth::default()));
+        }
+
+        if self.match_token(&[LEFTBRACKET]) {
+            return self.list();
+        }
+
+        // an expression never starts with a block, so a brace here always opens a map
+        if self.match_token(&[LEFTBRACE]) {
+            return self.map();
+        }
+
+        if self.match_token(&[FUN]) {
+            return self.lambda();
+        }
+        if self.check(LEFTPAREN) && self.is_arrow() {
+            return self.arrow();
         }
 
         if self.match_token(&[LEFTPAREN]) {
-            let expr = self.expression();
-            self.consume_token(RIGHTPAREN, "Expect ')' after expression.");
-            return Grouping(Box::new(expr));
-        } else {
-            Literal(None)
+            let expr = self.expression()?;
+            self.consume_token(RIGHTPAREN, "Expect ')' after expression.")?;
+            return Ok(Grouping(Box::new(expr)));
         }
+
+        Err(self.error(&self.peek(), "Expect expression."))
+    }
+
+    /// fun ( params ) { body }, which is called lambda in stack traces
+    fn lambda(&mut self) -> ParseResult {
+        let name = Token { lexeme: intern("lambda"), ..self.previous() };
+        self.consume_token(LEFTPAREN, "Expect '(' after 'fun'.")?;
+        let params = self.parameters()?;
+
+        self.consume_token(LEFTBRACE, "Expect '{' before lambda body.")?;
+        let body = self.block()?;
+        Ok(Lambda(Rc::new(Function { name, params, body, getter: false })))
     }
 
-    fn consume_token(&mut self, token_type: TokenType, _message: &str) -> Token {
+    /// ( params ) => expression, a lambda that returns the value of the expression
+    /// the expression doesn't extend past a comma, so arrow functions can be passed as arguments
+    fn arrow(&mut self) -> ParseResult {
+        let name = Token { lexeme: intern("lambda"), ..self.advance() };
+        let params = self.parameters()?;
+        let arrow = self.consume_token(ARROW, "Expect '=>' after parameters.")?;
+
+        let value = self.assignment()?;
+        Ok(Lambda(Rc::new(Function { name, params, body: vec![Stmt::Return(arrow, Some(value))], getter: false })))
+    }
+
+    /// whether the parenthesis at the current token opens the parameters of an arrow function,
+    /// which takes looking ahead for ( ) => or ( name , ... name ) =>
+    fn is_arrow(&self) -> bool {
+        let mut position = self.current + 1;
+        if self.tokens[position].token_type != RIGHTPAREN {
+            loop {
+                if self.tokens[position].token_type != IDENTIFIER {
+                    return false;
+                }
+                position += 1;
+                match self.tokens[position].token_type {
+                    COMMA => position += 1,
+                    RIGHTPAREN => break,
+                    _ => return false,
+                }
+            }
+        }
+        // the token list ends with EOF, so there is a token after the parenthesis
+        self.tokens[position + 1].token_type == ARROW
+    }
+
+    /// a string with interpolated expressions:
+    /// INTERPOLATION expression ( INTERPOLATION expression )* STRING
+    /// empty string parts are left out
+    fn interpolation(&mut self) -> ParseResult {
+        let mut parts = Vec::new();
+        let mut part = self.previous();
+
+        loop {
+            if part.literal != Text(intern("")) {
+                parts.push(Literal(part.literal));
+            }
+            if part.token_type == STRING {
+                return Ok(Interpolation(parts));
+            }
+
+            parts.push(self.expression()?);
+
+            if !self.match_token(&[INTERPOLATION]) {
+                self.consume_token(STRING, "Expect '}' after interpolated expression.")?;
+            }
+            part = self.previous();
+        }
+    }
+
+    /// list literal: [ ( expression ( "," expression )* )? ]
+    fn list(&mut self) -> ParseResult {
+        let mut elements = Vec::new();
+        if !self.check(RIGHTBRACKET) {
+            loop {
+                elements.push(self.assignment()?);
+                if !self.match_token(&[COMMA]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume_token(RIGHTBRACKET, "Expect ']' after list elements.")?;
+        Ok(Expr::List(elements))
+    }
+
+    /// map literal: { ( expression ":" expression ( "," expression ":" expression )* )? }
+    fn map(&mut self) -> ParseResult {
+        let brace = self.previous();
+
+        let mut entries = Vec::new();
+        if !self.check(RIGHTBRACE) {
+            loop {
+                let key = self.assignment()?;
+                self.consume_token(COLON, "Expect ':' after map key.")?;
+                let value = self.assignment()?;
+                entries.push((key, value));
+                if !self.match_token(&[COMMA]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume_token(RIGHTBRACE, "Expect '}' after map entries.")?;
+        Ok(Expr::Map(brace, entries))
+    }
+
+    fn consume_token(&mut self, token_type: TokenType, message: &str) -> Result<Token, ParseError> {
         if self.check(token_type) {
-            return self.advance();
+            return Ok(self.advance());
+        }
+
+        Err(self.error(&self.peek(), message))
+    }
+
+    /// discards tokens until the start of the next statement
+    fn synchronize(&mut self) {
+        self.advance();
+
+        while !self.is_at_end() {
+            if self.previous().token_type == SEMICOLON {
+                return;
+            }
+
+            match self.peek().token_type {
+                CLASS | FUN | VAR | FOR | IF | WHILE | PRINT | RETURN | BREAK | CONTINUE | THROW | TRY
+                | IMPORT | FROM | TRAIT => return,
+                _ => {
+                    self.advance();
+                }
+            }
         }
+    }
 
-        panic!()
+    /// prints the error and returns it, so that the caller can decide to unwind
+    fn error(&self, token: &Token, message: &str) -> ParseError {
+        report_error(token, message);
+        ParseError
     }
 }
 
+/// prints a compile error at the given token
+pub fn report_error(token: &Token, message: &str) {
+    if token.token_type == EOF {
+        println!("[line {} ] Error at end: {} ", token.line, message);
+    } else {
+        println!("[line {} ] Error at '{}': {} ", token.line, token.lexeme, message);
+    }
+}
@@ -1,30 +1,234 @@
+use crate::expression::AstPrinter;
 use crate::expression::Expr::Binary;
+use crate::interner::intern;
 use crate::expression::Expr::Literal;
 use crate::parser::parse;
 use crate::scanner::scan_tokens;
+use crate::statement::{Stmt, StmtVisitor};
 use crate::tokens::Token;
 use crate::tokens::TokenType::PLUS;
 use crate::tokens::Value::{None,Numeric};
 
+/// parses the source and prints the syntax tree of every statement on a line of its own
+fn print_ast(source: &str) -> String {
+    let tokens = scan_tokens(source).unwrap();
+    let statements = parse(tokens).unwrap();
+
+    statements.iter().map(|statement| AstPrinter {}.visit_stmt(statement)).collect::<Vec<String>>().join("\n")
+}
+
 #[test]
 fn test_scan_empty_source() {
     let tokens = scan_tokens("").unwrap();
-    let expression = parse(tokens);
+    let statements = parse(tokens).unwrap();
 
-    assert_eq!(expression, Literal(None));
+    assert_eq!(statements, vec![]);
 }
 
 #[test]
 fn test_scan_arithmetic() {
-    let tokens = scan_tokens("1+1").unwrap();
-    let expression = parse(tokens);
-
-    assert_eq!(expression, Binary(Box::new(Literal(Numeric(1.0))),
-                                  Token {
-                                      token_type: PLUS,
-                                      lexeme: String::from("+"),
-                                      literal: None,
-                                      line: 1,
-                                  },
-                                  Box::new(Literal(Numeric(1.0)))));
+    let tokens = scan_tokens("1+1;").unwrap();
+    let statements = parse(tokens).unwrap();
+
+    assert_eq!(statements, vec![Stmt::Expression(
+        Binary(Box::new(Literal(Numeric(1.0))),
+               Token {
+                   token_type: PLUS,
+                   lexeme: intern("+"),
+                   literal: None,
+                   line: 1,
+               },
+               Box::new(Literal(Numeric(1.0)))))]);
+}
+
+#[test]
+fn test_parse_list_literal() {
+    assert_eq!(print_ast("[1, 2 + 3, []];"), "(; (list 1 (+ 2 3) (list)))");
+}
+
+#[test]
+fn test_parse_index_slice_and_method_call() {
+    assert_eq!(print_ast("[1, 2][0] = [3][1:].len();"),
+               "(; (index= (list 1 2) 0 (call (. len (slice (list 3) 1 _)))))");
+}
+
+#[test]
+fn test_parse_missing_bracket() {
+    let tokens = scan_tokens("[1, 2;").unwrap();
+
+    assert!(parse(tokens).is_err());
+}
+
+#[test]
+fn test_parse_map_literal() {
+    assert_eq!(print_ast("var m = {\"a\": 1, 2: {}}[\"a\"];"), "(var m (index (map a 1 2 (map)) a))");
+}
+
+#[test]
+fn test_parse_map_missing_colon() {
+    let tokens = scan_tokens("var m = {\"a\" 1};").unwrap();
+
+    assert!(parse(tokens).is_err());
+}
+
+#[test]
+fn test_parse_block_is_not_a_map() {
+    assert_eq!(print_ast("{ var a = {}; a = 1; }"), "(block (var a (map)) (; (= a 1)))");
+}
+
+#[test]
+fn test_parse_for_in() {
+    assert_eq!(print_ast("for (x in [1, 2]) print x;"), "(for-in x (list 1 2) (print x))");
+    assert_eq!(print_ast("for (c in \"ab\") { print c; }"), "(for-in c ab (block (print c)))");
+}
+
+#[test]
+fn test_parse_for_in_missing_in() {
+    let tokens = scan_tokens("for (x [1, 2]) print x;").unwrap();
+
+    assert!(parse(tokens).is_err());
+}
+
+#[test]
+fn test_parse_if_and_while() {
+    assert_eq!(print_ast("if (a) print 1; else { break; }"), "(if a (print 1) (block (break)))");
+    assert_eq!(print_ast("while (a) continue;"), "(while a (continue))");
+}
+
+#[test]
+fn test_parse_for_loop() {
+    assert_eq!(print_ast("for (var i = 0; i < 3; i = i + 1) print i;"),
+               "(block (var i 0) (while (< i 3) (print i) (= i (+ i 1))))");
+    assert_eq!(print_ast("for (;;) {}"), "(while true (block))");
+}
+
+#[test]
+fn test_parse_conditional() {
+    assert_eq!(print_ast("a ? b : c ? d : e;"), "(; (?: a b (?: c d e)))");
+    assert_eq!(print_ast("a = b ? c : d;"), "(; (= a (?: b c d)))");
+    assert_eq!(print_ast("a == 1 ? b = 2 : c;"), "(; (?: (== a 1) (= b 2) c))");
+}
+
+#[test]
+fn test_parse_conditional_missing_colon() {
+    let tokens = scan_tokens("a ? b;").unwrap();
+
+    assert!(parse(tokens).is_err());
+}
+
+#[test]
+fn test_parse_logical() {
+    assert_eq!(print_ast("a or b and c == d ? e : f;"), "(; (?: (or a (and b (== c d))) e f))");
+    assert_eq!(print_ast("a and b and c;"), "(; (and (and a b) c))");
+}
+
+#[test]
+fn test_parse_comma() {
+    assert_eq!(print_ast("a = 1, b = 2, 3;"), "(; (, (, (= a 1) (= b 2)) 3))");
+    // commas in argument and element lists separate the elements
+    assert_eq!(print_ast("f(1, 2)[(3, 4)];"), "(; (index (call f 1 2) (group (, 3 4))))");
+    assert_eq!(print_ast("[1, (2, 3)];"), "(; (list 1 (group (, 2 3))))");
+}
+
+#[test]
+fn test_parse_exponent() {
+    assert_eq!(print_ast("-2 ** 2;"), "(; (- (** 2 2)))");
+    assert_eq!(print_ast("2 ** 3 ** 2;"), "(; (** 2 (** 3 2)))");
+    assert_eq!(print_ast("2 ** -1 * 3;"), "(; (* (** 2 (- 1)) 3))");
+}
+
+#[test]
+fn test_parse_arithmetic_and_bitwise_precedence() {
+    assert_eq!(print_ast("a + b % c ~/ d;"), "(; (+ a (~/ (% b c) d)))");
+    assert_eq!(print_ast("a | b ^ c & d << 1 + 2;"), "(; (| a (^ b (& c (<< d (+ 1 2))))))");
+    assert_eq!(print_ast("a & 1 == 0;"), "(; (== (& a 1) 0))");
+    assert_eq!(print_ast("~a >> 1;"), "(; (>> (~ a) 1))");
+}
+
+#[test]
+fn test_parse_compound_assignment() {
+    assert_eq!(print_ast("a += b -= 2;"), "(; (+= a (-= b 2)))");
+    assert_eq!(print_ast("xs[0] *= 2;"), "(; (*= (index xs 0) 2))");
+    assert_eq!(print_ast("-x++;"), "(; (- (post++ x)))");
+    assert_eq!(print_ast("--xs[i];"), "(; (-- (index xs i)))");
+    assert_eq!(print_ast("a.count += 1;"), "(; (+= (. count a) 1))");
+}
+
+#[test]
+fn test_parse_invalid_compound_assignment_target() {
+    assert!(parse(scan_tokens("1 += 2;").unwrap()).is_err());
+    assert!(parse(scan_tokens("(a) /= 2;").unwrap()).is_err());
+    assert!(parse(scan_tokens("++f();").unwrap()).is_err());
+    assert!(parse(scan_tokens("a++ ++;").unwrap()).is_err());
+}
+
+#[test]
+fn test_parse_string_interpolation() {
+    assert_eq!(print_ast("\"a ${x} b ${y + 1}\";"), "(; (interpolate a  x  b  (+ y 1)))");
+    assert_eq!(print_ast("\"${x}\";"), "(; (interpolate x))");
+    assert_eq!(print_ast("\"${ \"${x}\" }\";"), "(; (interpolate (interpolate x)))");
+}
+
+#[test]
+fn test_parse_empty_string_interpolation() {
+    assert!(parse(scan_tokens("\"${}\";").unwrap()).is_err());
+}
+
+#[test]
+fn test_parse_function_declaration() {
+    assert_eq!(print_ast("fun add(a, b) { return a + b; }"), "(fun add (a b) (return (+ a b)))");
+    assert_eq!(print_ast("fun f() { return; }"), "(fun f () (return))");
+    assert!(parse(scan_tokens("fun f(a,) {}").unwrap()).is_err());
+}
+
+#[test]
+fn test_parse_class_declaration() {
+    assert_eq!(print_ast("class A < B { init(x) { this.x = x; } get() { return super.get(); } }"),
+               "(class A < B (fun init (x) (; (.= x this x))) (fun get () (return (call (super get)))))");
+    assert_eq!(print_ast("class A {}"), "(class A)");
+    assert_eq!(print_ast("class A { area { return 1; } class make() { return A(); } class var count = 0; class var b; }"),
+               "(class A (get area (return 1)) (class (fun make () (return (call A)))) (class var count 0) (class var b))");
+    assert!(parse(scan_tokens("class A { class var x }").unwrap()).is_err());
+    assert!(parse(scan_tokens("class A { class }").unwrap()).is_err());
+}
+
+#[test]
+fn test_parse_traits() {
+    assert_eq!(print_ast("trait T { f() { return 1; } g { return 2; } }"), "(trait T (fun f () (return 1)) (get g (return 2)))");
+    assert_eq!(print_ast("class A < B with T, U {}"), "(class A < B with T U)");
+    assert_eq!(print_ast("class A with T { f() {} }"), "(class A with T (fun f ()))");
+    assert!(parse(scan_tokens("class A with {}").unwrap()).is_err());
+    assert!(parse(scan_tokens("trait T { class f() {} }").unwrap()).is_err());
+    assert!(parse(scan_tokens("class A { fun f() {} }").unwrap()).is_err());
+    assert!(parse(scan_tokens("super;").unwrap()).is_err());
+}
+
+#[test]
+fn test_parse_lambdas() {
+    assert_eq!(print_ast("var f = fun (a, b) { return a + b; };"), "(var f (lambda (a b) (return (+ a b))))");
+    assert_eq!(print_ast("f((a) => a * 2, () => 1, 3);"), "(; (call f (lambda (a) (return (* a 2))) (lambda () (return 1)) 3))");
+    assert_eq!(print_ast("(a, b) => (c) => a;"), "(; (lambda (a b) (return (lambda (c) (return a)))))");
+    // without a name fun starts an expression statement
+    assert_eq!(print_ast("fun () {}();"), "(; (call (lambda ())))");
+    assert_eq!(print_ast("fun f() {}"), "(fun f ())");
+    assert_eq!(print_ast("(a) + (b, c);"), "(; (+ (group a) (group (, b c))))");
+    assert!(parse(scan_tokens("(a, 1) => a;").unwrap()).is_err());
+    assert!(parse(scan_tokens("fun () {}").unwrap()).is_err());
+}
+
+#[test]
+fn test_parse_throw_and_try() {
+    assert_eq!(print_ast("throw \"boom\";"), "(throw boom)");
+    assert_eq!(print_ast("try { f(); } catch (e) { print e; } finally { g(); }"),
+               "(try (block (; (call f))) (catch e (print e)) (finally (; (call g))))");
+    assert_eq!(print_ast("try {} finally {}"), "(try (block) (finally))");
+    assert!(parse(scan_tokens("try {}").unwrap()).is_err());
+}
+
+#[test]
+fn test_parse_imports() {
+    assert_eq!(print_ast("import \"lib.lox\" as lib;"), "(import \"lib.lox\" as lib)");
+    assert_eq!(print_ast("from \"lib.lox\" import a, b;"), "(from \"lib.lox\" import a b)");
+    assert!(parse(scan_tokens("import \"lib.lox\";").unwrap()).is_err());
+    assert!(parse(scan_tokens("from lib import a;").unwrap()).is_err());
 }
@@ -0,0 +1,54 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::native::NativeFunction;
+use crate::tokens::Value;
+
+/// the seed until a script calls seed(), so that runs are reproducible
+const DEFAULT_SEED: u64 = 42;
+
+/// random(), randomInt(a, b) and seed(n), which share the state of one generator
+pub fn natives() -> Vec<NativeFunction> {
+    let state = Rc::new(Cell::new(DEFAULT_SEED));
+
+    let random = Rc::clone(&state);
+    let random_int = Rc::clone(&state);
+    vec![
+        // a number from 0 up to but not including 1
+        NativeFunction::new("random", 0, move |_, _| {
+            Ok(Value::Numeric((next(&random) >> 11) as f64 / (1u64 << 53) as f64))
+        }),
+        // an integer from a up to and including b
+        NativeFunction::new("randomInt", 2, move |_, arguments| {
+            let (low, high) = (integer("randomInt", &arguments[0])?, integer("randomInt", &arguments[1])?);
+            if low > high {
+                return Err(format!("randomInt() range {} to {} is empty.", low, high));
+            }
+            let size = (high as i128 - low as i128 + 1) as u128;
+            Ok(Value::Numeric((low as i128 + (next(&random_int) as u128 % size) as i128) as f64))
+        }),
+        NativeFunction::new("seed", 1, move |_, arguments| {
+            state.set(integer("seed", &arguments[0])? as u64);
+            Ok(Value::None)
+        }),
+    ]
+}
+
+/// the splitmix64 generator, which is small and good enough for scripts, but not for cryptography
+fn next(state: &Cell<u64>) -> u64 {
+    state.set(state.get().wrapping_add(0x9E37_79B9_7F4A_7C15));
+    let mut z = state.get();
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// numbers outside of the range of i64 are rejected instead of saturating
+fn integer(name: &str, value: &Value) -> Result<i64, String> {
+    return match value {
+        Value::Numeric(number) if number.fract() == 0.0 && *number >= i64::MIN as f64 && *number < i64::MAX as f64 => {
+            Ok(*number as i64)
+        }
+        _ => Err(format!("Arguments of {}() must be integers.", name)),
+    };
+}
@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+
+use crate::class::conflict;
+use crate::expression::{Depth, Expr, Visitor};
+use crate::interner::{intern, Symbol};
+use crate::parser::report_error;
+use crate::statement::{Class, Function, Stmt, StmtVisitor};
+use crate::tokens::Token;
+
+/// public function for the static checks that run after parsing and before interpreting,
+/// which also determines the scope that every variable refers to, so that closures see the variables
+/// that were declared where the function is written, and not those declared later
+/// errors are reported as soon as they are found
+pub fn resolve(statements: &[Stmt]) -> Result<(), &'static str> {
+    let mut resolver = Resolver {
+        scopes: Vec::new(),
+        loop_depth: 0,
+        function_type: FunctionType::None,
+        class_type: ClassType::None,
+        traits: HashMap::new(),
+        error_occurred: false,
+    };
+    resolver.resolve_statements(statements);
+
+    return if resolver.error_occurred { Err("Error occurred") } else { Ok(()) };
+}
+
+/// the kind of function around the current statement
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+/// the kind of class around the current statement
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+    Trait,
+}
+
+/// struct used internally to keep state while walking the statements
+struct Resolver {
+    // the local scopes around the current statement, the innermost last, the global scope is not in it
+    // every variable declared in a scope is true once its initializer has been resolved
+    scopes: Vec<HashMap<Symbol, bool>>,
+
+    // number of loops around the current statement
+    loop_depth: usize,
+
+    function_type: FunctionType,
+
+    class_type: ClassType,
+
+    // the names of the methods of every trait declared so far, to find conflicts between the traits of a class
+    // traits that are declared elsewhere, like in modules, are checked when the class is declared
+    traits: HashMap<Symbol, Vec<Symbol>>,
+
+    error_occurred: bool,
+}
+
+impl StmtVisitor<()> for Resolver {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(statements) => {
+                self.begin_scope();
+                self.resolve_statements(statements);
+                self.end_scope();
+            }
+            Stmt::Break(keyword) | Stmt::Continue(keyword) => {
+                if self.loop_depth == 0 {
+                    self.error(keyword, &format!("Can't use '{}' outside of a loop.", keyword.lexeme));
+                }
+            }
+            Stmt::Class(class) => {
+                self.define(&class.name);
+                if let Some(superclass) = &class.superclass {
+                    if superclass.name().lexeme == class.name.lexeme {
+                        self.error(superclass.name(), "A class can't inherit from itself.");
+                    }
+                    self.visit_expr(superclass);
+                }
+                self.resolve_expressions(&class.traits);
+
+                // the fields of the class are initialized in the scope around it, where this is not bound
+                for initializer in class.class_fields.iter().filter_map(|(_, initializer)| initializer.as_ref()) {
+                    self.visit_expr(initializer);
+                }
+
+                self.check_traits(class);
+
+                let enclosing = self.class_type;
+                self.class_type = ClassType::Class;
+                // the methods of a subclass find super in a scope around the one with this
+                if class.superclass.is_some() {
+                    self.class_type = ClassType::Subclass;
+                    self.begin_scope();
+                    self.define_name("super");
+                }
+                self.begin_scope();
+                self.define_name("this");
+
+                for method in &class.methods {
+                    let function_type = if method.name.lexeme == "init" { FunctionType::Initializer } else { FunctionType::Method };
+                    self.resolve_function(method, function_type);
+                }
+                // in class methods this is the class
+                for method in &class.class_methods {
+                    self.resolve_function(method, FunctionType::Method);
+                }
+
+                self.end_scope();
+                if class.superclass.is_some() {
+                    self.end_scope();
+                }
+                self.class_type = enclosing;
+            }
+            Stmt::Expression(expression) | Stmt::Print(expression) | Stmt::Throw(_, expression) => {
+                self.visit_expr(expression);
+            }
+            Stmt::ForIn(name, iterable, body) => {
+                self.visit_expr(iterable);
+                // every iteration gets a fresh variable, in a scope of its own around the body
+                self.begin_scope();
+                self.define(name);
+                self.loop_depth += 1;
+                self.visit_stmt(body);
+                self.loop_depth -= 1;
+                self.end_scope();
+            }
+            Stmt::Function(function) => {
+                // defined before the body is resolved, so that the function can call itself
+                self.define(&function.name);
+                self.resolve_function(function, FunctionType::Function);
+            }
+            Stmt::If(condition, then_branch, else_branch) => {
+                self.visit_expr(condition);
+                self.visit_stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.visit_stmt(else_branch);
+                }
+            }
+            Stmt::FromImport(path, names) => {
+                self.check_import(path);
+                for name in names {
+                    self.define(name);
+                }
+            }
+            Stmt::Import(path, name) => {
+                self.check_import(path);
+                self.define(name);
+            }
+            Stmt::Return(keyword, value) => {
+                if self.function_type == FunctionType::None {
+                    self.error(keyword, "Can't return from top-level code.");
+                }
+                if let Some(value) = value {
+                    // init always returns the instance
+                    if self.function_type == FunctionType::Initializer {
+                        self.error(keyword, "Can't return a value from an initializer.");
+                    }
+                    self.visit_expr(value);
+                }
+            }
+            Stmt::Trait(declaration) => {
+                self.define(&declaration.name);
+                let methods = declaration.methods.iter().map(|method| method.name.lexeme.clone()).collect();
+                self.traits.insert(declaration.name.lexeme.clone(), methods);
+
+                let enclosing = std::mem::replace(&mut self.class_type, ClassType::Trait);
+                self.begin_scope();
+                self.define_name("this");
+                for method in &declaration.methods {
+                    let function_type = if method.name.lexeme == "init" { FunctionType::Initializer } else { FunctionType::Method };
+                    self.resolve_function(method, function_type);
+                }
+                self.end_scope();
+                self.class_type = enclosing;
+            }
+            Stmt::Try(body, catch, finally) => {
+                self.begin_scope();
+                self.resolve_statements(body);
+                self.end_scope();
+                if let Some((name, catch)) = catch {
+                    self.begin_scope();
+                    self.define(name);
+                    self.resolve_statements(catch);
+                    self.end_scope();
+                }
+                if let Some(finally) = finally {
+                    self.begin_scope();
+                    self.resolve_statements(finally);
+                    self.end_scope();
+                }
+            }
+            Stmt::Var(name, initializer) => {
+                self.declare(name);
+                if let Some(initializer) = initializer {
+                    self.visit_expr(initializer);
+                }
+                self.define(name);
+            }
+            Stmt::While(condition, body, increment) => {
+                self.visit_expr(condition);
+                self.loop_depth += 1;
+                self.visit_stmt(body);
+                self.loop_depth -= 1;
+                if let Some(increment) = increment {
+                    self.visit_expr(increment);
+                }
+            }
+        }
+    }
+}
+
+impl Visitor<()> for Resolver {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Assign(name, value, depth) => {
+                self.visit_expr(value);
+                self.resolve_local(name, depth);
+            }
+            Expr::Get(value, _) | Expr::Grouping(value) | Expr::Increment(value, _, _) | Expr::Unary(_, value) => {
+                self.visit_expr(value);
+            }
+            Expr::Binary(left, _, right) | Expr::CompoundAssign(left, _, right) | Expr::Index(left, _, right)
+            | Expr::Logical(left, _, right) | Expr::Set(left, _, right) => {
+                self.visit_expr(left);
+                self.visit_expr(right);
+            }
+            Expr::Call(callee, _, arguments) => {
+                self.visit_expr(callee);
+                self.resolve_expressions(arguments);
+            }
+            Expr::Conditional(condition, then_branch, else_branch) => {
+                self.visit_expr(condition);
+                self.visit_expr(then_branch);
+                self.visit_expr(else_branch);
+            }
+            Expr::IndexSet(object, _, index, value) => {
+                self.visit_expr(object);
+                self.visit_expr(index);
+                self.visit_expr(value);
+            }
+            Expr::Lambda(function) => {
+                self.resolve_function(function, FunctionType::Function);
+            }
+            Expr::Interpolation(expressions) | Expr::List(expressions) => {
+                self.resolve_expressions(expressions);
+            }
+            Expr::Map(_, entries) => {
+                for (key, value) in entries {
+                    self.visit_expr(key);
+                    self.visit_expr(value);
+                }
+            }
+            Expr::Slice(object, _, start, end) => {
+                self.visit_expr(object);
+                for bound in start.iter().chain(end.iter()) {
+                    self.visit_expr(bound);
+                }
+            }
+            Expr::Super(keyword, _, depth) => {
+                match self.class_type {
+                    ClassType::None => self.error(keyword, "Can't use 'super' outside of a class."),
+                    ClassType::Class => self.error(keyword, "Can't use 'super' in a class with no superclass."),
+                    ClassType::Trait => self.error(keyword, "Can't use 'super' in a trait."),
+                    ClassType::Subclass => {}
+                }
+                self.resolve_local(keyword, depth);
+            }
+            Expr::This(keyword, depth) => {
+                if self.class_type == ClassType::None {
+                    self.error(keyword, "Can't use 'this' outside of a class.");
+                }
+                self.resolve_local(keyword, depth);
+            }
+            Expr::Variable(name, depth) => {
+                if self.scopes.last().and_then(|scope| scope.get(&name.lexeme)) == Some(&false) {
+                    self.error(name, "Can't read local variable in its own initializer.");
+                }
+                self.resolve_local(name, depth);
+            }
+            Expr::Literal(_) => {}
+        }
+    }
+}
+
+impl Resolver {
+    fn resolve_statements(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            self.visit_stmt(statement);
+        }
+    }
+
+    fn resolve_expressions(&mut self, expressions: &[Expr]) {
+        for expression in expressions {
+            self.visit_expr(expression);
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// adds the variable to the innermost scope, it can't be used until it is defined
+    /// global variables are not tracked
+    fn declare(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), false);
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), true);
+        }
+    }
+
+    /// defines this or super, which the interpreter binds in a scope of their own
+    fn define_name(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(intern(name), true);
+        }
+    }
+
+    /// the number of scopes out to the innermost one that declares the variable,
+    /// or to the global scope when no local scope does
+    fn resolve_local(&mut self, name: &Token, depth: &Depth) {
+        let declared = self.scopes.iter().rev().position(|scope| scope.contains_key(&name.lexeme));
+        depth.set(declared.unwrap_or(self.scopes.len()));
+    }
+
+    /// paths are relative to the file that is running, which is only known for sure at the top level
+    fn check_import(&mut self, path: &Token) {
+        if self.function_type != FunctionType::None {
+            self.error(path, "Can't import inside a function.");
+        }
+    }
+
+    /// two traits of the class can't define the same method, unless the class defines it too
+    fn check_traits(&mut self, class: &Class) {
+        // the trait that each method comes from
+        let mut origins: HashMap<Symbol, Symbol> = HashMap::new();
+        for name in class.traits.iter().map(Expr::name) {
+            let methods = match self.traits.get(&name.lexeme) {
+                Some(methods) => methods.clone(),
+                None => continue,
+            };
+            for method in methods {
+                if class.methods.iter().any(|declaration| declaration.name.lexeme == method) {
+                    continue;
+                }
+                match origins.get(&method) {
+                    // using the same trait twice is not a conflict
+                    Some(origin) if *origin != name.lexeme => {
+                        let message = conflict(origin, &name.lexeme, &method);
+                        self.error(name, &message);
+                    }
+                    _ => {
+                        origins.insert(method, name.lexeme.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// the parameters and the body share one scope, like they do when the function is called
+    fn resolve_function(&mut self, function: &Function, function_type: FunctionType) {
+        // break and continue can't jump out of the function to a loop around the declaration
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let enclosing = std::mem::replace(&mut self.function_type, function_type);
+        self.begin_scope();
+        for param in &function.params {
+            self.define(param);
+        }
+        self.resolve_statements(&function.body);
+        self.end_scope();
+        self.function_type = enclosing;
+        self.loop_depth = loop_depth;
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        self.error_occurred = true;
+        report_error(token, message);
+    }
+}
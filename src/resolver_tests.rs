@@ -0,0 +1,79 @@
+use crate::parser::parse;
+use crate::resolver::resolve;
+use crate::scanner::scan_tokens;
+
+fn resolves(source: &str) -> bool {
+    let tokens = scan_tokens(source).unwrap();
+    let statements = parse(tokens).unwrap();
+    resolve(&statements).is_ok()
+}
+
+#[test]
+fn test_break_and_continue_inside_loops() {
+    assert!(resolves("while (true) break;"));
+    assert!(resolves("for (;;) { if (true) continue; }"));
+    assert!(resolves("for (x in [1]) { { break; } }"));
+}
+
+#[test]
+fn test_break_and_continue_outside_of_loops() {
+    assert!(!resolves("break;"));
+    assert!(!resolves("if (true) continue;"));
+    assert!(!resolves("while (true) {} break;"));
+}
+
+#[test]
+fn test_return_outside_of_functions() {
+    assert!(resolves("fun f() { if (true) return 1; return; }"));
+    assert!(!resolves("return 1;"));
+    assert!(resolves("var f = fun () { return 1; };"));
+    assert!(!resolves("while (true) { var f = () => 1; fun () { break; }; }"));
+    assert!(!resolves("try { return; } finally {}"));
+}
+
+#[test]
+fn test_break_does_not_leave_functions() {
+    assert!(resolves("fun f() { while (true) break; }"));
+    assert!(!resolves("while (true) { fun f() { break; } }"));
+}
+
+#[test]
+fn test_imports_at_the_top_level() {
+    assert!(resolves("if (true) { import \"lib.lox\" as lib; }"));
+    assert!(!resolves("fun f() { from \"lib.lox\" import a; }"));
+}
+
+#[test]
+fn test_this_and_super_inside_classes() {
+    assert!(resolves("class A < B { f() { fun g() { return this; } return super.f(); } }"));
+    assert!(!resolves("print this;"));
+    assert!(!resolves("fun f() { return super.f(); }"));
+    assert!(!resolves("class A { f() { super.f(); } }"));
+    assert!(!resolves("class A < A {}"));
+    assert!(resolves("class A { class f() { return this; } g { return this; } }"));
+    assert!(!resolves("class A { class var x = this; }"));
+}
+
+#[test]
+fn test_no_value_returned_from_initializers() {
+    assert!(resolves("class A { init() { return; } }"));
+    assert!(!resolves("class A { init() { return 1; } }"));
+}
+
+#[test]
+fn test_trait_method_conflicts() {
+    assert!(!resolves("trait A { f() {} } trait B { f() {} } class C with A, B {}"));
+    assert!(resolves("trait A { f() {} } trait B { f() {} } class C with A, B { f() {} }"));
+    assert!(resolves("trait A { f() {} } class C with A, A {}"));
+    assert!(!resolves("trait A { f() { return super.f(); } }"));
+    assert!(resolves("trait A { f() { return this; } }"));
+}
+
+#[test]
+fn test_local_variable_in_its_own_initializer() {
+    assert!(!resolves("{ var a = a; }"));
+    assert!(!resolves("fun f() { var a = [a]; }"));
+    assert!(resolves("var a = 1; { var b = a; }"));
+    // global variables can be redefined with their old value
+    assert!(resolves("var a = 1; var a = a;"));
+}
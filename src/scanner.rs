@@ -1,3 +1,4 @@
+use crate::interner::intern;
 use crate::keywords::KEYWORDS;
 use crate::tokens::{Token, TokenType, Value};
 use crate::tokens::TokenType::*;
@@ -12,9 +13,13 @@ pub fn scan_tokens(source: &str) -> Result<Vec<Token>, &'static str> {
         scanner.scan_token()
     }
 
+    if !scanner.interpolations.is_empty() {
+        scanner.report_error(scanner.line, "unterminated string interpolation");
+    }
+
     scanner.tokens.push(Token {
         token_type: EOF,
-        lexeme: String::new(),
+        lexeme: intern(""),
         literal: Value::None,
         line: scanner.line,
     });
@@ -45,12 +50,16 @@ struct Scanner<'a> {
 
     // current line (mainly used to report the line after a compilation error occurred)
     line: usize,
+
+    // one entry for every string interpolation ${...} that is being scanned,
+    // counting the braces opened inside it that are not closed yet
+    interpolations: Vec<usize>,
 }
 
 impl Scanner<'_> {
     /// create Scanner struct using the source
-    fn new(source: &str) -> Scanner {
-        Scanner { tokens: Vec::new(), source, start: 0, current: 0, line: 1, error_occured: false }
+    fn new(source: &str) -> Scanner<'_> {
+        Scanner { tokens: Vec::new(), source, start: 0, current: 0, line: 1, error_occured: false, interpolations: Vec::new() }
     }
 
     ///scans the source, character by character
@@ -61,19 +70,79 @@ impl Scanner<'_> {
         match next_char {
             '(' => self.add_token(LEFTPAREN),
             ')' => self.add_token(RIGHTPAREN),
-            '{' => self.add_token(LEFTBRACE),
-            '}' => self.add_token(RIGHTBRACE),
+            '{' => {
+                if let Some(open_braces) = self.interpolations.last_mut() {
+                    *open_braces += 1;
+                }
+                self.add_token(LEFTBRACE);
+            }
+            '}' => {
+                match self.interpolations.last_mut() {
+                    // this brace closes the interpolation, the string continues after it
+                    Some(0) => {
+                        self.interpolations.pop();
+                        self.string();
+                    }
+                    Some(open_braces) => {
+                        *open_braces -= 1;
+                        self.add_token(RIGHTBRACE);
+                    }
+                    None => self.add_token(RIGHTBRACE),
+                }
+            }
+            '[' => self.add_token(LEFTBRACKET),
+            ']' => self.add_token(RIGHTBRACKET),
+            ':' => self.add_token(COLON),
             ',' => self.add_token(COMMA),
             '.' => self.add_token(DOT),
-            '-' => self.add_token(MINUS),
-            '+' => self.add_token(PLUS),
+            '-' => {
+                let token = if self.match_char('=') {
+                    MINUSEQUAL
+                } else if self.match_char('-') {
+                    MINUSMINUS
+                } else {
+                    MINUS
+                };
+                self.add_token(token);
+            }
+            '+' => {
+                let token = if self.match_char('=') {
+                    PLUSEQUAL
+                } else if self.match_char('+') {
+                    PLUSPLUS
+                } else {
+                    PLUS
+                };
+                self.add_token(token);
+            }
             ';' => self.add_token(SEMICOLON),
-            '*' => self.add_token(STAR),
+            '%' => self.add_token(PERCENT),
+            '&' => self.add_token(AMPERSAND),
+            '|' => self.add_token(PIPE),
+            '^' => self.add_token(CARET),
+            '~' => {
+                // integer division is ~/ like in dart, because // starts a comment
+                let token = if self.match_char('/') { TILDESLASH } else { TILDE };
+                self.add_token(token);
+            }
+            '?' => self.add_token(QUESTION),
+            '*' => {
+                let token = if self.match_char('*') {
+                    STARSTAR
+                } else if self.match_char('=') {
+                    STAREQUAL
+                } else {
+                    STAR
+                };
+                self.add_token(token);
+            }
             '/' => {
                 if self.match_char('/') {
                     while self.peek(0) != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                } else if self.match_char('=') {
+                    self.add_token(SLASHEQUAL);
                 } else {
                     self.add_token(SLASH);
                 }
@@ -83,15 +152,33 @@ impl Scanner<'_> {
                 self.add_token(token);
             }
             '=' => {
-                let token = if self.match_char('=') { EQUALEQUAL } else { EQUAL };
+                let token = if self.match_char('=') {
+                    EQUALEQUAL
+                } else if self.match_char('>') {
+                    ARROW
+                } else {
+                    EQUAL
+                };
                 self.add_token(token);
             }
             '>' => {
-                let token = if self.match_char('=') { GREATEREQUAL } else { GREATER };
+                let token = if self.match_char('=') {
+                    GREATEREQUAL
+                } else if self.match_char('>') {
+                    GREATERGREATER
+                } else {
+                    GREATER
+                };
                 self.add_token(token);
             }
             '<' => {
-                let token = if self.match_char('=') { LESSEQUAL } else { LESS };
+                let token = if self.match_char('=') {
+                    LESSEQUAL
+                } else if self.match_char('<') {
+                    LESSLESS
+                } else {
+                    LESS
+                };
                 self.add_token(token);
             }
             '\n' => {
@@ -102,7 +189,7 @@ impl Scanner<'_> {
             '\r' => {}
             '\"' => self.string(),
             _ => {
-                if next_char.is_digit(10) {
+                if next_char.is_ascii_digit() {
                     self.number();
                 } else if is_alphabetic_or_underscore(next_char) {
                     self.identifier();
@@ -132,14 +219,14 @@ impl Scanner<'_> {
    /// advances while characters are considered part of the number
    /// finally adds a number token to the list.
     fn number(&mut self) {
-        while self.peek(0).is_digit(10) {
+        while self.peek(0).is_ascii_digit() {
             self.advance();
         }
 
-        if self.peek(0) == '.' && self.peek(1).is_digit(10) {
+        if self.peek(0) == '.' && self.peek(1).is_ascii_digit() {
             self.advance();
 
-            while self.peek(0).is_digit(10) {
+            while self.peek(0).is_ascii_digit() {
                 self.advance();
             }
         }
@@ -151,8 +238,19 @@ impl Scanner<'_> {
     /// handle string literals
     /// advances until a terminating double quote is found and then adds the string token to the list
     /// raises an interpreter error when the double-quote is not found and the end of the source has been reached
+    /// the string can contain interpolations ${...}, then the text before ${ is added as an INTERPOLATION token,
+    /// the expression inside is scanned as normal tokens and the string continues after the closing brace
     fn string(&mut self) {
         while self.peek(0) != '\"' && !self.is_at_end() {
+            if self.peek(0) == '$' && self.peek(1) == '{' {
+                let value = intern(&self.source[self.start + 1..self.current]);
+                self.add_token_literal(INTERPOLATION, Value::Text(value));
+
+                self.advance();
+                self.advance();
+                self.interpolations.push(0);
+                return;
+            }
             if self.peek(0) == '\n' {
                 self.line += 1;
             }
@@ -164,28 +262,30 @@ impl Scanner<'_> {
         } else {
             self.advance();
 
-            let value = String::from(&self.source[self.start + 1..self.current - 1]);
+            let value = intern(&self.source[self.start + 1..self.current - 1]);
             self.add_token_literal(STRING, Value::Text(value));
         }
     }
 
     /// advance (consume) one character and return that
+    /// current is a byte index, so it moves by the length of the character in utf-8
     fn advance(&mut self) -> char {
-        self.current += 1;
-        self.source[self.current - 1..self.current].chars().next().unwrap()
+        let next_char = self.source[self.current..].chars().next().unwrap();
+        self.current += next_char.len_utf8();
+        next_char
     }
 
     /// adds a token of the given type
     fn add_token(&mut self, token_type: TokenType) {
         let text = &self.source[self.start..self.current];
-        let token = Token { token_type: token_type, lexeme: String::from(text), literal: Value::None, line: self.line };
+        let token = Token { token_type, lexeme: intern(text), literal: Value::None, line: self.line };
         self.tokens.push(token);
     }
 
     /// adds a token of the given type and content
     fn add_token_literal(&mut self, token_type: TokenType, literal: Value) {
         let text = &self.source[self.start..self.current];
-        let token = Token { token_type: token_type, lexeme: String::from(text), literal, line: self.line };
+        let token = Token { token_type, lexeme: intern(text), literal, line: self.line };
         self.tokens.push(token);
     }
 
@@ -198,24 +298,17 @@ impl Scanner<'_> {
     /// the integer ahead parameter can be used to look farther ahead.
     /// peek(0) is the first etc.
     fn peek(&self, ahead: usize) -> char {
-        if self.is_at_end() {
-            '\0'
-        } else {
-            self.source[self.current + ahead..self.current + ahead + 1].chars().next().unwrap()
-        }
+        self.source[self.current..].chars().nth(ahead).unwrap_or('\0')
     }
 
     /// Advances only if the next character matches the given expected character and returns true,
     /// or only returns false if there is no match.
     fn match_char(&mut self, expected: char) -> bool {
-        if self.is_at_end() {
-            return false;
-        }
-        if self.source[self.current..self.current + 1].chars().next().unwrap() != expected {
+        if self.is_at_end() || self.peek(0) != expected {
             return false;
         }
 
-        self.current += 1;
+        self.current += expected.len_utf8();
         true
     }
 
@@ -232,5 +325,5 @@ fn is_alphabetic_or_underscore(c: char) -> bool {
 }
 
 fn is_alphanumeric(c: char) -> bool {
-    is_alphabetic_or_underscore(c) || c.is_digit(10)
+    is_alphabetic_or_underscore(c) || c.is_ascii_digit()
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 #[cfg(test)]
 use crate::scanner::scan_tokens;
+use crate::interner::{intern, table_size};
 use crate::tokens::TokenType::*;
 use crate::tokens::Value::{Numeric, Text};
 
@@ -8,7 +9,7 @@ fn test_scan_empty_source() {
     let tokens = scan_tokens("").unwrap();
     assert_eq!(tokens.len(), 1);
 
-    let token = tokens.get(0).unwrap();
+    let token = tokens.first().unwrap();
     assert_eq!(token.token_type, EOF);
     assert_eq!(token.line, 1);
 }
@@ -18,7 +19,7 @@ fn test_scan_single_char_tokens() {
     let tokens = scan_tokens(">").unwrap();
     assert_eq!(tokens.len(), 2);
 
-    let token = tokens.get(0).unwrap();
+    let token = tokens.first().unwrap();
     assert_eq!(token.token_type, GREATER);
     assert_eq!(token.lexeme, ">");
 
@@ -31,7 +32,7 @@ fn test_scan_double_char_tokens() {
     let tokens = scan_tokens(">=").unwrap();
     assert_eq!(tokens.len(), 2);
 
-    let token = tokens.get(0).unwrap();
+    let token = tokens.first().unwrap();
     assert_eq!(token.token_type, GREATEREQUAL);
     assert_eq!(token.lexeme, ">=");
 }
@@ -42,7 +43,7 @@ fn test_scan_string_literals() {
     let tokens = scan_tokens("\"hello world\"").unwrap();
     assert_eq!(tokens.len(), 2);
 
-    let token = tokens.get(0).unwrap();
+    let token = tokens.first().unwrap();
     assert_eq!(token.token_type, STRING);
     assert_eq!(token.lexeme, "\"hello world\"");
     match token.literal.clone() {
@@ -58,7 +59,7 @@ fn test_scan_numeric_literals() {
     let tokens = scan_tokens("0.1").unwrap();
     assert_eq!(tokens.len(), 2);
 
-    let token = tokens.get(0).unwrap();
+    let token = tokens.first().unwrap();
     assert_eq!(token.token_type, NUMBER);
     assert_eq!(token.lexeme, "0.1");
     match token.literal {
@@ -74,7 +75,7 @@ fn test_keywords() {
     let tokens = scan_tokens("fun").unwrap();
     assert_eq!(tokens.len(), 2);
 
-    let token = tokens.get(0).unwrap();
+    let token = tokens.first().unwrap();
     assert_eq!(token.token_type, FUN);
 }
 
@@ -83,7 +84,7 @@ fn test_identifiers() {
     let tokens = scan_tokens("a").unwrap();
     assert_eq!(tokens.len(), 2);
 
-    let token = tokens.get(0).unwrap();
+    let token = tokens.first().unwrap();
     assert_eq!(token.token_type, IDENTIFIER);
 }
 
@@ -92,7 +93,7 @@ fn test_expression() {
     let tokens = scan_tokens("if a == 1 {b=\"hello world\"}").unwrap();
     assert_eq!(tokens.len(), 10);
 
-    assert_eq!(tokens.get(0).unwrap().token_type, IF);
+    assert_eq!(tokens.first().unwrap().token_type, IF);
     assert_eq!(tokens.get(1).unwrap().token_type, IDENTIFIER);
     assert_eq!(tokens.get(2).unwrap().token_type, EQUALEQUAL);
     assert_eq!(tokens.get(3).unwrap().token_type, NUMBER);
@@ -102,4 +103,110 @@ fn test_expression() {
     assert_eq!(tokens.get(7).unwrap().token_type, STRING);
     assert_eq!(tokens.get(8).unwrap().token_type, RIGHTBRACE);
     assert_eq!(tokens.get(9).unwrap().token_type, EOF);
-}
\ No newline at end of file
+}
+#[test]
+fn test_identifiers_are_interned() {
+    let tokens = scan_tokens("a = \"a\" + a").unwrap();
+
+    let first = &tokens[0].lexeme;
+    let second = &tokens[4].lexeme;
+    assert_eq!(first, second);
+    assert_eq!(first.as_ptr(), second.as_ptr());
+
+    match tokens[2].literal.clone() {
+        Text(value) => {
+            assert_eq!(&value, first);
+        }
+        _ => { assert_eq!(true, false, "token value != a") }
+    }
+}
+
+#[test]
+fn test_unused_strings_are_freed() {
+    let before = table_size();
+    let symbol = intern("only used here");
+    let copy = symbol.clone();
+    assert_eq!(table_size(), before + 1);
+    assert_eq!(intern("only used here").as_ptr(), symbol.as_ptr());
+
+    drop(symbol);
+    assert_eq!(table_size(), before + 1);
+    drop(copy);
+    assert_eq!(table_size(), before);
+}
+
+#[test]
+fn test_operator_tokens() {
+    let tokens = scan_tokens("% ** & | ^ ~ << >> <= >= =>").unwrap();
+    let types: Vec<_> = tokens.iter().map(|token| token.token_type).collect();
+
+    assert_eq!(types, vec![PERCENT, STARSTAR, AMPERSAND, PIPE, CARET, TILDE, LESSLESS, GREATERGREATER,
+                           LESSEQUAL, GREATEREQUAL, ARROW, EOF]);
+}
+
+#[test]
+fn test_integer_division() {
+    let tokens = scan_tokens("7 ~/ 2 ~ 1").unwrap();
+    let types: Vec<_> = tokens.iter().map(|token| token.token_type).collect();
+    assert_eq!(types, vec![NUMBER, TILDESLASH, NUMBER, TILDE, NUMBER, EOF]);
+}
+
+#[test]
+fn test_comment_after_operand() {
+    let tokens = scan_tokens("if (x > 1) // explain\nx = 1;").unwrap();
+    let types: Vec<_> = tokens.iter().map(|token| token.token_type).collect();
+    assert_eq!(types, vec![IF, LEFTPAREN, IDENTIFIER, GREATER, NUMBER, RIGHTPAREN, IDENTIFIER, EQUAL, NUMBER,
+                           SEMICOLON, EOF]);
+
+    let tokens = scan_tokens("class A < B // note\n").unwrap();
+    let types: Vec<_> = tokens.iter().map(|token| token.token_type).collect();
+    assert_eq!(types, vec![CLASS, IDENTIFIER, LESS, IDENTIFIER, EOF]);
+
+    let tokens = scan_tokens("// 7 // 2\nx = 1; // comment").unwrap();
+    let types: Vec<_> = tokens.iter().map(|token| token.token_type).collect();
+    assert_eq!(types, vec![IDENTIFIER, EQUAL, NUMBER, SEMICOLON, EOF]);
+}
+
+#[test]
+fn test_compound_assignment_tokens() {
+    let tokens = scan_tokens("+= -= *= /= ++ -- + - * / **").unwrap();
+    let types: Vec<_> = tokens.iter().map(|token| token.token_type).collect();
+
+    assert_eq!(types, vec![PLUSEQUAL, MINUSEQUAL, STAREQUAL, SLASHEQUAL, PLUSPLUS, MINUSMINUS,
+                           PLUS, MINUS, STAR, SLASH, STARSTAR, EOF]);
+}
+
+#[test]
+fn test_string_interpolation() {
+    let tokens = scan_tokens("\"a ${x} b ${y + 1}\"").unwrap();
+    let types: Vec<_> = tokens.iter().map(|token| token.token_type).collect();
+
+    assert_eq!(types, vec![INTERPOLATION, IDENTIFIER, INTERPOLATION, IDENTIFIER, PLUS, NUMBER, STRING, EOF]);
+    assert_eq!(tokens[0].literal, Text(intern("a ")));
+    assert_eq!(tokens[2].literal, Text(intern(" b ")));
+    assert_eq!(tokens[6].literal, Text(intern("")));
+}
+
+#[test]
+fn test_string_interpolation_with_nested_braces_and_strings() {
+    let tokens = scan_tokens("\"${ {\"k\": \"${v}\"} }!\"").unwrap();
+    let types: Vec<_> = tokens.iter().map(|token| token.token_type).collect();
+
+    assert_eq!(types, vec![INTERPOLATION, LEFTBRACE, STRING, COLON, INTERPOLATION, IDENTIFIER, STRING,
+                           RIGHTBRACE, STRING, EOF]);
+    assert_eq!(tokens[8].literal, Text(intern("!")));
+}
+
+#[test]
+fn test_unterminated_string_interpolation() {
+    assert!(scan_tokens("\"a ${x\"").is_err());
+    assert!(scan_tokens("\"a ${x} b").is_err());
+}
+
+#[test]
+fn test_scan_unicode() {
+    let tokens = scan_tokens("\"héllo 🌍\" + ünïcode").unwrap();
+    assert_eq!(tokens[0].literal, Text(intern("héllo 🌍")));
+    assert_eq!(tokens[2].token_type, IDENTIFIER);
+    assert_eq!(tokens[2].lexeme, "ünïcode");
+}
@@ -0,0 +1,204 @@
+use std::rc::Rc;
+
+use crate::expression::{AstPrinter, Expr, Visitor};
+use crate::tokens::Token;
+
+pub trait StmtVisitor<R> {
+    fn visit_stmt(&mut self, stmt: &Stmt) -> R;
+}
+
+#[derive(Debug, PartialOrd, PartialEq)]
+pub enum Stmt {
+    Block(Vec<Stmt>),
+    Break(Token),
+    Class(Class),
+    Continue(Token),
+    Expression(Expr),
+    // loop variable, iterable, body
+    ForIn(Token, Expr, Box<Stmt>),
+    // shared with the functions that executing the declaration creates
+    Function(Rc<Function>),
+    // module path, imported names
+    FromImport(Token, Vec<Token>),
+    // condition, then branch, optional else branch
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+    // module path, the name of the variable the module is assigned to
+    Import(Token, Token),
+    Print(Expr),
+    // keyword, optional value
+    Return(Token, Option<Expr>),
+    // keyword, the thrown value
+    Throw(Token, Expr),
+    Trait(Trait),
+    // body, optional catch clause with the name of the error variable, optional finally clause
+    Try(Vec<Stmt>, Option<(Token, Vec<Stmt>)>, Option<Vec<Stmt>>),
+    // name, optional initializer
+    Var(Token, Option<Expr>),
+    // condition, body, optional increment that runs after the body, also after continue
+    While(Expr, Box<Stmt>, Option<Expr>),
+}
+
+/// a class declaration
+#[derive(Debug, PartialOrd, PartialEq)]
+pub struct Class {
+    pub name: Token,
+    // a variable
+    pub superclass: Option<Expr>,
+    // the variables of the traits whose methods the class gets, in the order of the declaration
+    pub traits: Vec<Expr>,
+    pub methods: Vec<Rc<Function>>,
+    // the methods declared with the class prefix, which are called on the class itself
+    pub class_methods: Vec<Rc<Function>>,
+    // the fields of the class itself, with their optional initializers
+    pub class_fields: Vec<(Token, Option<Expr>)>,
+}
+
+/// a trait declaration, the methods are added to the classes that use the trait
+#[derive(Debug, PartialOrd, PartialEq)]
+pub struct Trait {
+    pub name: Token,
+    pub methods: Vec<Rc<Function>>,
+}
+
+/// a function declaration, or a method declaration inside a class
+#[derive(Debug, PartialOrd, PartialEq)]
+pub struct Function {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+    // a method declared without a parameter list, which runs when the property is read
+    pub getter: bool,
+}
+
+impl StmtVisitor<String> for AstPrinter {
+    fn visit_stmt(&mut self, stmt: &Stmt) -> String {
+        return match stmt {
+            Stmt::Block(statements) => {
+                self.block("block", statements)
+            }
+            Stmt::Break(_) => {
+                String::from("(break)")
+            }
+            Stmt::Class(class) => {
+                let mut buf = format!("(class {}", class.name.lexeme);
+                if let Some(superclass) = &class.superclass {
+                    buf.push_str(&format!(" < {}", self.visit_expr(superclass)));
+                }
+                if !class.traits.is_empty() {
+                    let traits: Vec<String> = class.traits.iter().map(|name| self.visit_expr(name)).collect();
+                    buf.push_str(&format!(" with {}", traits.join(" ")));
+                }
+                for method in &class.methods {
+                    buf.push(' ');
+                    buf.push_str(&self.function(method));
+                }
+                for method in &class.class_methods {
+                    buf.push_str(&format!(" (class {})", self.function(method)));
+                }
+                for (name, initializer) in &class.class_fields {
+                    match initializer {
+                        Some(initializer) => buf.push_str(&format!(" (class var {} {})", name.lexeme, self.visit_expr(initializer))),
+                        None => buf.push_str(&format!(" (class var {})", name.lexeme)),
+                    }
+                }
+                buf.push(')');
+                buf
+            }
+            Stmt::Continue(_) => {
+                String::from("(continue)")
+            }
+            Stmt::Expression(expression) => {
+                format!("(; {})", self.visit_expr(expression))
+            }
+            Stmt::ForIn(name, iterable, body) => {
+                format!("(for-in {} {} {})", name.lexeme, self.visit_expr(iterable), self.visit_stmt(body))
+            }
+            Stmt::Function(function) => {
+                self.function(function)
+            }
+            Stmt::FromImport(path, names) => {
+                let names: Vec<&str> = names.iter().map(|name| name.lexeme.as_str()).collect();
+                format!("(from {} import {})", path.lexeme, names.join(" "))
+            }
+            Stmt::If(condition, then_branch, else_branch) => {
+                match else_branch {
+                    Some(else_branch) => format!("(if {} {} {})", self.visit_expr(condition),
+                                                 self.visit_stmt(then_branch), self.visit_stmt(else_branch)),
+                    None => format!("(if {} {})", self.visit_expr(condition), self.visit_stmt(then_branch)),
+                }
+            }
+            Stmt::Import(path, name) => {
+                format!("(import {} as {})", path.lexeme, name.lexeme)
+            }
+            Stmt::Print(expression) => {
+                format!("(print {})", self.visit_expr(expression))
+            }
+            Stmt::Return(_, value) => {
+                match value {
+                    Some(value) => format!("(return {})", self.visit_expr(value)),
+                    None => String::from("(return)"),
+                }
+            }
+            Stmt::Throw(_, value) => {
+                format!("(throw {})", self.visit_expr(value))
+            }
+            Stmt::Trait(declaration) => {
+                let mut buf = format!("(trait {}", declaration.name.lexeme);
+                for method in &declaration.methods {
+                    buf.push(' ');
+                    buf.push_str(&self.function(method));
+                }
+                buf.push(')');
+                buf
+            }
+            Stmt::Try(body, catch, finally) => {
+                let mut buf = format!("(try {}", self.block("block", body));
+                if let Some((name, catch)) = catch {
+                    buf.push(' ');
+                    buf.push_str(&self.block(&format!("catch {}", name.lexeme), catch));
+                }
+                if let Some(finally) = finally {
+                    buf.push(' ');
+                    buf.push_str(&self.block("finally", finally));
+                }
+                buf.push(')');
+                buf
+            }
+            Stmt::Var(name, initializer) => {
+                match initializer {
+                    Some(initializer) => format!("(var {} {})", name.lexeme, self.visit_expr(initializer)),
+                    None => format!("(var {})", name.lexeme),
+                }
+            }
+            Stmt::While(condition, body, increment) => {
+                match increment {
+                    Some(increment) => format!("(while {} {} {})", self.visit_expr(condition),
+                                               self.visit_stmt(body), self.visit_expr(increment)),
+                    None => format!("(while {} {})", self.visit_expr(condition), self.visit_stmt(body)),
+                }
+            }
+        };
+    }
+}
+
+#[allow(dead_code)]
+impl AstPrinter {
+    /// prints the statements in parentheses after the given name
+    fn block(&mut self, name: &str, statements: &[Stmt]) -> String {
+        let mut buf = format!("({}", name);
+        for statement in statements {
+            buf.push(' ');
+            buf.push_str(&self.visit_stmt(statement));
+        }
+        buf.push(')');
+        buf
+    }
+
+    fn function(&mut self, function: &Function) -> String {
+        if function.getter {
+            return self.block(&format!("get {}", function.name.lexeme), &function.body);
+        }
+        let params: Vec<&str> = function.params.iter().map(|param| param.lexeme.as_str()).collect();
+        self.block(&format!("fun {} ({})", function.name.lexeme, params.join(" ")), &function.body)
+    }
+}
@@ -0,0 +1,193 @@
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use crate::interner::{intern, Symbol};
+use crate::iterator;
+use crate::list;
+use crate::native::NativeFunction;
+use crate::tokens::Value;
+
+type StringFn = fn(&str, &[Value]) -> Result<Value, String>;
+
+/// the functions that are methods of strings, with the number of arguments they take besides the string
+/// except for len they are also global functions, that take the string as the first argument
+/// indexes count characters (unicode scalar values), not bytes
+const FUNCTIONS: [(&str, usize, StringFn); 12] = [
+    ("len", 0, len),
+    ("substr", 2, substr),
+    ("indexOf", 1, index_of),
+    ("split", 1, split),
+    ("replace", 2, replace),
+    ("trim", 0, trim),
+    ("upper", 0, upper),
+    ("lower", 0, lower),
+    ("startsWith", 1, starts_with),
+    ("endsWith", 1, ends_with),
+    ("charAt", 1, char_at),
+    ("ord", 0, ord),
+];
+
+/// returns the method with the given name bound to the string,
+/// or None if strings don't have a method with that name
+pub fn method(text: &Symbol, name: &str) -> Option<Value> {
+    let text = text.clone();
+    let method = match name {
+        "iterator" => NativeFunction::new("iterator", 0, move |_, _| {
+            Ok(iterator::of_text(&text))
+        }),
+        _ => {
+            let &(name, arity, function) = FUNCTIONS.iter().find(|(function, _, _)| *function == name)?;
+            NativeFunction::new(name, arity, move |_, arguments| function(&text, arguments))
+        }
+    };
+    Some(Value::Native(Rc::new(method)))
+}
+
+/// the global string functions, and len, join and chr
+pub fn natives() -> Vec<NativeFunction> {
+    let mut natives: Vec<NativeFunction> = FUNCTIONS.iter()
+        .filter(|(name, _, _)| *name != "len")
+        .map(|&(name, arity, function)| NativeFunction::new(name, arity + 1, move |_, arguments| {
+            function(text(name, &arguments[0])?, &arguments[1..])
+        }))
+        .collect();
+
+    // the length of a string, list or map
+    natives.push(NativeFunction::new("len", 1, |_, arguments| {
+        return match &arguments[0] {
+            Value::Text(text) => len(text, &[]),
+            Value::List(elements) => Ok(Value::Numeric(elements.borrow().len() as f64)),
+            Value::Map(map) => Ok(Value::Numeric(map.borrow().entries().len() as f64)),
+            _ => Err(String::from("Argument of len() must be a string, list or map.")),
+        };
+    }));
+    natives.push(NativeFunction::new("join", 2, |_, arguments| {
+        return match &arguments[0] {
+            Value::List(elements) => join(&elements.borrow(), &arguments[1]),
+            _ => Err(String::from("Argument of join() must be a list.")),
+        };
+    }));
+    natives.push(NativeFunction::new("chr", 1, |_, arguments| {
+        let code = integer("chr", &arguments[0])?;
+        return match u32::try_from(code).ok().and_then(char::from_u32) {
+            Some(character) => Ok(Value::Text(intern(&character.to_string()))),
+            None => Err(format!("Invalid character code {}.", code)),
+        };
+    }));
+    natives
+}
+
+/// the character at the given index, as a string
+pub fn get(text: &str, index: &Value) -> Result<Value, String> {
+    char_at(text, std::slice::from_ref(index))
+}
+
+/// returns the characters from start (inclusive) to end (exclusive), like slicing a list
+pub fn slice(text: &str, start: Option<&Value>, end: Option<&Value>) -> Result<Value, String> {
+    let (start, end) = list::bounds(text.chars().count(), start, end)?;
+    Ok(Value::Text(intern(&text.chars().skip(start).take(end - start).collect::<String>())))
+}
+
+/// concatenates the strings in the list, with the separator between them
+pub fn join(elements: &[Value], separator: &Value) -> Result<Value, String> {
+    let separator = text("join", separator)?;
+    let mut parts = Vec::new();
+    for element in elements {
+        match element {
+            Value::Text(part) => parts.push(part.as_str()),
+            _ => return Err(String::from("join() can only join strings.")),
+        }
+    }
+    Ok(Value::Text(intern(&parts.join(separator))))
+}
+
+fn len(text: &str, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Numeric(text.chars().count() as f64))
+}
+
+/// the given number of characters from start, or fewer at the end of the string
+fn substr(text: &str, arguments: &[Value]) -> Result<Value, String> {
+    let start = list::index(&arguments[0], text.chars().count() + 1)?;
+    let length = integer("substr", &arguments[1])?;
+    if length < 0 {
+        return Err(format!("Length {} is negative.", length));
+    }
+    Ok(Value::Text(intern(&text.chars().skip(start).take(length as usize).collect::<String>())))
+}
+
+/// the index of the first occurrence, or -1
+fn index_of(text: &str, arguments: &[Value]) -> Result<Value, String> {
+    let needle = self::text("indexOf", &arguments[0])?;
+    return match text.find(needle) {
+        Some(byte) => Ok(Value::Numeric(text[..byte].chars().count() as f64)),
+        None => Ok(Value::Numeric(-1.0)),
+    };
+}
+
+/// splitting with an empty separator gives the characters
+fn split(text: &str, arguments: &[Value]) -> Result<Value, String> {
+    let separator = self::text("split", &arguments[0])?;
+    let parts: Vec<Value> = if separator.is_empty() {
+        text.chars().map(|character| Value::Text(intern(&character.to_string()))).collect()
+    } else {
+        text.split(separator).map(|part| Value::Text(intern(part))).collect()
+    };
+    Ok(Value::list(parts))
+}
+
+/// replaces every occurrence
+fn replace(text: &str, arguments: &[Value]) -> Result<Value, String> {
+    let from = self::text("replace", &arguments[0])?;
+    let to = self::text("replace", &arguments[1])?;
+    Ok(Value::Text(intern(&text.replace(from, to))))
+}
+
+fn trim(text: &str, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Text(intern(text.trim())))
+}
+
+fn upper(text: &str, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Text(intern(&text.to_uppercase())))
+}
+
+fn lower(text: &str, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Text(intern(&text.to_lowercase())))
+}
+
+fn starts_with(text: &str, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Boolean(text.starts_with(self::text("startsWith", &arguments[0])?)))
+}
+
+fn ends_with(text: &str, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Boolean(text.ends_with(self::text("endsWith", &arguments[0])?)))
+}
+
+fn char_at(text: &str, arguments: &[Value]) -> Result<Value, String> {
+    let index = list::index(&arguments[0], text.chars().count())?;
+    // the index is in range, so there is a character
+    let character = text.chars().nth(index).unwrap();
+    Ok(Value::Text(intern(&character.to_string())))
+}
+
+/// the code point of a string with a single character
+fn ord(text: &str, _: &[Value]) -> Result<Value, String> {
+    let mut characters = text.chars();
+    return match (characters.next(), characters.next()) {
+        (Some(character), None) => Ok(Value::Numeric(character as u32 as f64)),
+        _ => Err(String::from("ord() needs a string of one character.")),
+    };
+}
+
+fn text<'a>(name: &str, value: &'a Value) -> Result<&'a str, String> {
+    return match value {
+        Value::Text(text) => Ok(text.as_str()),
+        _ => Err(format!("Argument of {}() must be a string.", name)),
+    };
+}
+
+fn integer(name: &str, value: &Value) -> Result<i64, String> {
+    return match value {
+        Value::Numeric(number) if number.fract() == 0.0 => Ok(*number as i64),
+        _ => Err(format!("Argument of {}() must be an integer.", name)),
+    };
+}
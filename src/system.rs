@@ -0,0 +1,96 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use crate::interner::intern;
+use crate::interpreter::Interpreter;
+use crate::native::NativeFunction;
+use crate::tokens::Value;
+
+/// the functions for files, standard input, the environment and exiting
+/// failures are runtime errors, so scripts can catch them
+pub fn natives() -> Vec<NativeFunction> {
+    vec![
+        NativeFunction::new("readFile", 1, |interpreter, arguments| {
+            let path = file_path(interpreter, "readFile", &arguments[0])?;
+            let text = fs::read_to_string(path).map_err(|error| failure("read", path, error))?;
+            Ok(Value::Text(intern(&text)))
+        }),
+        NativeFunction::new("writeFile", 2, |interpreter, arguments| {
+            let path = file_path(interpreter, "writeFile", &arguments[0])?;
+            let text = text("writeFile", &arguments[1])?;
+            fs::write(path, text).map_err(|error| failure("write", path, error))?;
+            Ok(Value::None)
+        }),
+        NativeFunction::new("appendFile", 2, |interpreter, arguments| {
+            let path = file_path(interpreter, "appendFile", &arguments[0])?;
+            let text = text("appendFile", &arguments[1])?;
+            OpenOptions::new().create(true).append(true).open(path)
+                .and_then(|mut file| file.write_all(text.as_bytes()))
+                .map_err(|error| failure("append to", path, error))?;
+            Ok(Value::None)
+        }),
+        NativeFunction::new("exists", 1, |interpreter, arguments| {
+            let path = file_path(interpreter, "exists", &arguments[0])?;
+            Ok(Value::Boolean(fs::metadata(path).is_ok()))
+        }),
+        // the names of the entries, sorted
+        NativeFunction::new("listDir", 1, |interpreter, arguments| {
+            let path = file_path(interpreter, "listDir", &arguments[0])?;
+            let mut names = Vec::new();
+            for entry in fs::read_dir(path).map_err(|error| failure("list", path, error))? {
+                let entry = entry.map_err(|error| failure("list", path, error))?;
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+            names.sort();
+            Ok(Value::list(names.iter().map(|name| Value::Text(intern(name))).collect()))
+        }),
+        // a line without the line ending, or nil at the end of the input
+        NativeFunction::new("readLine", 0, |_, _| {
+            let mut line = String::new();
+            let read = io::stdin().lock().read_line(&mut line)
+                .map_err(|error| format!("Can't read from standard input: {}.", error))?;
+            if read == 0 {
+                return Ok(Value::None);
+            }
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            Ok(Value::Text(intern(line.strip_suffix('\r').unwrap_or(line))))
+        }),
+        // nil if the variable is not set
+        NativeFunction::new("getenv", 1, |_, arguments| {
+            let name = text("getenv", &arguments[0])?;
+            return match std::env::var(name) {
+                Ok(value) => Ok(Value::Text(intern(&value))),
+                Err(_) => Ok(Value::None),
+            };
+        }),
+        NativeFunction::new("exit", 1, |_, arguments| {
+            return match arguments[0] {
+                Value::Numeric(code) if code.fract() == 0.0 && code >= i32::MIN as f64 && code <= i32::MAX as f64 => {
+                    io::stdout().flush().ok();
+                    process::exit(code as i32)
+                }
+                _ => Err(String::from("Exit code must be an integer.")),
+            };
+        }),
+    ]
+}
+
+/// the path argument of a function that accesses files, which is an error when file access is denied
+fn file_path<'a>(interpreter: &Interpreter, name: &str, value: &'a Value) -> Result<&'a str, String> {
+    if !interpreter.file_access() {
+        return Err(String::from("File access is denied."));
+    }
+    text(name, value)
+}
+
+fn text<'a>(name: &str, value: &'a Value) -> Result<&'a str, String> {
+    return match value {
+        Value::Text(text) => Ok(text.as_str()),
+        _ => Err(format!("Argument of {}() must be a string.", name)),
+    };
+}
+
+fn failure(action: &str, path: &str, error: io::Error) -> String {
+    format!("Can't {} '{}': {}.", action, path, error)
+}
@@ -1,32 +1,201 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::fmt;
+use std::rc::Rc;
 
-#[derive(Clone, PartialOrd, PartialEq)]
+use crate::class::{LoxClass, LoxInstance, LoxTrait};
+use crate::function::LoxFunction;
+use crate::gc;
+use crate::interner::Symbol;
+use crate::interpreter::LoxError;
+use crate::iterator::LoxIterator;
+use crate::map::Map;
+use crate::module::Module;
+use crate::native::NativeFunction;
+
+#[derive(Clone)]
 pub enum Value {
-    Text(String),
+    Text(Symbol),
     Numeric(f64),
     Boolean(bool),
+    // lists are shared and mutable, so copies of the value see each others changes
+    List(Rc<RefCell<Vec<Value>>>),
+    Map(Rc<RefCell<Map>>),
+    Native(Rc<NativeFunction>),
+    Function(Rc<LoxFunction>),
+    Class(Rc<LoxClass>),
+    // fields are shared and mutable, like the elements of lists
+    Instance(Rc<LoxInstance>),
+    Trait(Rc<LoxTrait>),
+    Iterator(Rc<LoxIterator>),
+    // what a catch clause receives
+    Error(Rc<LoxError>),
+    Module(Rc<Module>),
     None,
 }
 
-impl fmt::Debug for Value {
+impl Value {
+    /// creates a list value containing the given elements
+    pub fn list(elements: Vec<Value>) -> Value {
+        Value::List(gc::manage(RefCell::new(elements)))
+    }
+
+    /// creates a map value that shares the given map
+    pub fn map(map: Map) -> Value {
+        Value::Map(gc::manage(RefCell::new(map)))
+    }
+
+    pub fn native(function: NativeFunction) -> Value {
+        Value::Native(Rc::new(function))
+    }
+}
+
+/// lists, maps, functions, classes, instances, traits, iterators, errors and modules are compared by identity,
+/// the other values by content
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        return match (self, other) {
+            (Value::Text(left), Value::Text(right)) => left == right,
+            (Value::Numeric(left), Value::Numeric(right)) => left == right,
+            (Value::Boolean(left), Value::Boolean(right)) => left == right,
+            (Value::List(left), Value::List(right)) => Rc::ptr_eq(left, right),
+            (Value::Map(left), Value::Map(right)) => Rc::ptr_eq(left, right),
+            (Value::Native(left), Value::Native(right)) => Rc::ptr_eq(left, right),
+            (Value::Function(left), Value::Function(right)) => Rc::ptr_eq(left, right),
+            (Value::Class(left), Value::Class(right)) => Rc::ptr_eq(left, right),
+            (Value::Instance(left), Value::Instance(right)) => Rc::ptr_eq(left, right),
+            (Value::Trait(left), Value::Trait(right)) => Rc::ptr_eq(left, right),
+            (Value::Iterator(left), Value::Iterator(right)) => Rc::ptr_eq(left, right),
+            (Value::Error(left), Value::Error(right)) => Rc::ptr_eq(left, right),
+            (Value::Module(left), Value::Module(right)) => Rc::ptr_eq(left, right),
+            (Value::None, Value::None) => true,
+            _ => false,
+        };
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        return match (self, other) {
+            (Value::Text(left), Value::Text(right)) => left.partial_cmp(right),
+            (Value::Numeric(left), Value::Numeric(right)) => left.partial_cmp(right),
+            (Value::Boolean(left), Value::Boolean(right)) => left.partial_cmp(right),
+            _ if self == other => Some(Ordering::Equal),
+            _ => Option::None,
+        };
+    }
+}
+
+/// the text that print shows, which is what the reference implementation prints for the values it has
+/// strings inside lists and maps are not quoted
+impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Value::Text(value) => {
-                write!(f, "{}", value.to_string())
+        write_value(self, f, &mut Vec::new())
+    }
+}
+
+/// open holds the lists and maps that are being written around the value,
+/// a list or map inside itself is written as [...] or {...}
+fn write_value(value: &Value, f: &mut fmt::Formatter<'_>, open: &mut Vec<*const ()>) -> fmt::Result {
+    match value {
+        Value::Text(value) => {
+            write!(f, "{}", value)
+        }
+        Value::Numeric(value) => {
+            write!(f, "{}", format_number(*value))
+        }
+        Value::Boolean(value) => {
+            write!(f, "{}", value)
+        }
+        Value::List(elements) => {
+            let pointer = Rc::as_ptr(elements) as *const ();
+            if open.contains(&pointer) {
+                return write!(f, "[...]");
             }
-            Value::Numeric(value) => {
-                write!(f, "{}", value)
+            open.push(pointer);
+            write!(f, "[")?;
+            for (index, element) in elements.borrow().iter().enumerate() {
+                if index > 0 { write!(f, ", ")?; }
+                write_value(element, f, open)?;
             }
-            Value::Boolean(value) => {
-                write!(f, "{}", value)
+            open.pop();
+            write!(f, "]")
+        }
+        Value::Map(map) => {
+            let pointer = Rc::as_ptr(map) as *const ();
+            if open.contains(&pointer) {
+                return write!(f, "{{...}}");
             }
-            Value::None => {
-                write!(f, "Nil")
+            open.push(pointer);
+            write!(f, "{{")?;
+            for (index, (key, value)) in map.borrow().entries().iter().enumerate() {
+                if index > 0 { write!(f, ", ")?; }
+                write_value(key, f, open)?;
+                write!(f, ": ")?;
+                write_value(value, f, open)?;
             }
+            open.pop();
+            write!(f, "}}")
+        }
+        Value::Native(_) => {
+            write!(f, "<native fn>")
+        }
+        Value::Function(function) => {
+            write!(f, "<fn {}>", function.declaration.name.lexeme)
+        }
+        Value::Class(class) => {
+            write!(f, "{}", class.name)
+        }
+        Value::Instance(instance) => {
+            write!(f, "{} instance", instance.class.name)
+        }
+        Value::Trait(declaration) => {
+            write!(f, "<trait {}>", declaration.name)
+        }
+        Value::Iterator(_) => {
+            write!(f, "<iterator>")
+        }
+        Value::Error(error) => {
+            write!(f, "<error {}>", error.message)
+        }
+        Value::Module(module) => {
+            write!(f, "<module {}>", module.name())
+        }
+        Value::None => {
+            write!(f, "nil")
         }
     }
 }
 
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// formats the number like java's Double.toString, without the .0 that integers end with, as jlox does:
+/// 1, 2.5, -0, 1.0E7, 1.5E-5, NaN, Infinity
+pub fn format_number(number: f64) -> String {
+    if number.is_nan() {
+        return String::from("NaN");
+    }
+    if number.is_infinite() {
+        return String::from(if number > 0.0 { "Infinity" } else { "-Infinity" });
+    }
+
+    let magnitude = number.abs();
+    if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        // rust prints the shortest digits that read back as the same number, like java, and integers without .0
+        return number.to_string();
+    }
+
+    // java switches to scientific notation outside of that range, with at least one digit after the point
+    let scientific = format!("{:e}", number);
+    let (mantissa, exponent) = scientific.split_at(scientific.find('e').unwrap());
+    let point = if mantissa.contains('.') { "" } else { ".0" };
+    format!("{}{}E{}", mantissa, point, &exponent[1..])
+}
+
 /// struct that contains a single token
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
 pub struct Token {
@@ -34,7 +203,7 @@ pub struct Token {
     pub token_type: TokenType,
 
     // the actual part of the code that resulted in this token
-    pub lexeme: String,
+    pub lexeme: Symbol,
 
     // numeric (ie 1,2, 1.0 etc) and alphanumeric (any quoted text) values
     pub literal: Value,
@@ -43,6 +212,7 @@ pub struct Token {
     pub line: usize,
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd)]
 pub enum TokenType {
     // Single-character tokens.
@@ -50,6 +220,9 @@ pub enum TokenType {
     RIGHTPAREN,
     LEFTBRACE,
     RIGHTBRACE,
+    LEFTBRACKET,
+    RIGHTBRACKET,
+    COLON,
     COMMA,
     DOT,
     MINUS,
@@ -57,6 +230,12 @@ pub enum TokenType {
     SEMICOLON,
     STAR,
     SLASH,
+    PERCENT,
+    AMPERSAND,
+    PIPE,
+    CARET,
+    TILDE,
+    QUESTION,
 
     // One or two character tokens.
     BANG,
@@ -67,29 +246,54 @@ pub enum TokenType {
     GREATEREQUAL,
     LESS,
     LESSEQUAL,
+    STARSTAR,
+    TILDESLASH,
+    PLUSEQUAL,
+    MINUSEQUAL,
+    STAREQUAL,
+    SLASHEQUAL,
+    PLUSPLUS,
+    MINUSMINUS,
+    LESSLESS,
+    GREATERGREATER,
+    ARROW,
 
     // Literals.
     STRING,
+    // the part of a string before an interpolated expression
+    INTERPOLATION,
     NUMBER,
     IDENTIFIER,
 
     // Keywords.
     AND,
+    AS,
+    BREAK,
+    CATCH,
     CLASS,
+    CONTINUE,
     ELSE,
     FALSE,
+    FINALLY,
     FUN,
     FOR,
+    FROM,
     IF,
+    IMPORT,
+    IN,
     NIL,
     OR,
     PRINT,
     RETURN,
     SUPER,
     THIS,
+    THROW,
+    TRAIT,
     TRUE,
+    TRY,
     VAR,
     WHILE,
+    WITH,
 
     EOF,         // end of file
 }
\ No newline at end of file
@@ -0,0 +1,61 @@
+use crate::interner::intern;
+use crate::native::NativeFunction;
+use crate::tokens::Value;
+
+/// type(), str() and num()
+pub fn natives() -> Vec<NativeFunction> {
+    vec![
+        NativeFunction::new("type", 1, |_, arguments| {
+            Ok(Value::Text(intern(&type_name(&arguments[0]))))
+        }),
+        // the text print would show, so toString methods are used
+        NativeFunction::new("str", 1, |interpreter, arguments| {
+            let text = interpreter.stringify(&arguments[0]).map_err(|error| error.message)?;
+            Ok(Value::Text(intern(&text)))
+        }),
+        NativeFunction::new("num", 1, |_, arguments| {
+            return match &arguments[0] {
+                Value::Numeric(number) => Ok(Value::Numeric(*number)),
+                Value::Text(text) => parse_number(text.trim())
+                    .map(Value::Numeric)
+                    .ok_or_else(|| format!("Can't convert '{}' to a number.", text)),
+                _ => Err(String::from("Argument of num() must be a number or a string.")),
+            };
+        }),
+    ]
+}
+
+/// nil, boolean, number, string, list, map, function, class, trait, iterator, error or module,
+/// and the name of the class for instances
+fn type_name(value: &Value) -> String {
+    let name = match value {
+        Value::None => "nil",
+        Value::Boolean(_) => "boolean",
+        Value::Numeric(_) => "number",
+        Value::Text(_) => "string",
+        Value::List(_) => "list",
+        Value::Map(_) => "map",
+        Value::Native(_) | Value::Function(_) => "function",
+        Value::Class(_) => "class",
+        Value::Instance(instance) => return instance.class.name.to_string(),
+        Value::Trait(_) => "trait",
+        Value::Iterator(_) => "iterator",
+        Value::Error(_) => "error",
+        Value::Module(_) => "module",
+    };
+    String::from(name)
+}
+
+/// a number written like a number literal, optionally with a minus sign: -?[0-9]+(.[0-9]+)?
+fn parse_number(text: &str) -> Option<f64> {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    let (integer, fraction) = match digits.find('.') {
+        Some(point) => (&digits[..point], Some(&digits[point + 1..])),
+        None => (digits, None),
+    };
+    let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+    if !is_digits(integer) || !fraction.is_none_or(is_digits) {
+        return None;
+    }
+    text.parse().ok()
+}